@@ -4,28 +4,313 @@ use ink_lang as ink;
 
 #[ink::contract]
 mod incrementer {
+    use ink_prelude::vec::Vec;
 
+    /// How many of the latest `inc` calls `recent_events` remembers.
+    const EVENT_LOG_CAPACITY: u32 = 8;
+
+    /// Bumped whenever the storage layout below changes, so upgraded code can tell which
+    /// layout it is reading and migrate from it.
+    const STORAGE_VERSION: u16 = 12;
+
+    /// Default length of the window `daily_quota` applies to: 24 hours, in milliseconds.
+    const ONE_DAY: Timestamp = 24 * 60 * 60 * 1000;
+
+    /// Storage is laid out in field order: new fields go at the end and existing ones are never
+    /// reordered or removed, so code swapped in with `set_code_hash` keeps reading the same cells.
     #[ink(storage)]
     pub struct Incrementer {
         value: i32,
         my_value: ink_storage::collections::HashMap<AccountId, i32>,
+        named: ink_storage::collections::HashMap<Hash, i32>,
+        personal_floor: i32,
+        min_increment: i32,
+        locked: bool,
+        event_log: ink_storage::collections::Vec<(i32, AccountId)>,
+        inc_count: u64,
+        owner: AccountId,
+        mine_paused: bool,
+        version: u16,
+        applied_keys: ink_storage::collections::HashMap<Hash, ()>,
+        finalized: bool,
+        reputation: ink_storage::collections::HashMap<AccountId, u32>,
+        daily_quota: i32,
+        quota_window: Timestamp,
+        /// When each caller's current quota window started, and how much `inc_mine` added in it.
+        quota_used: ink_storage::collections::HashMap<AccountId, (Timestamp, i32)>,
+        auto_pause_at: Option<i32>,
+        /// Set once `value` crosses `auto_pause_at`; only the owner can clear it.
+        paused: bool,
+        /// Block of the latest shared increment, from `inc` or `multicall`.
+        last_inc_block: Option<BlockNumber>,
+        policy: IncrementPolicy,
+        /// Highest `value` ever held; it never decreases.
+        high_water_mark: i32,
+        /// ed25519 key whose signed reports `inc_by_oracle` accepts.
+        oracle: Option<AccountId>,
+        /// Signed into each report, so a report can't be applied twice.
+        oracle_nonce: u64,
+        /// Personal operations each account has applied since the owner last reset the counts.
+        op_count: ink_storage::collections::HashMap<AccountId, u64>,
+        /// Slot of the oldest `event_log` entry, which the next one overwrites once the log is full.
+        event_log_head: u32,
+    }
+
+    /// The owner wound the contract down, folding every personal value into the shared one.
+    #[ink(event)]
+    pub struct WoundDown {
+        total_absorbed: i32,
+    }
+
+    /// The owner exchanged the personal values of `a` and `b`.
+    #[ink(event)]
+    pub struct MineSwapped {
+        a: AccountId,
+        b: AccountId,
+    }
+
+    /// The owner zeroed the shared value, which stood at `previous` after `inc_count_at_reset` increments.
+    #[ink(event)]
+    pub struct Reset {
+        previous: i32,
+        inc_count_at_reset: u64,
+    }
+
+    /// A `multicall` applied `ops` operations, leaving the shared value at `final_value`.
+    #[ink(event)]
+    pub struct BatchApplied {
+        ops: u32,
+        final_value: i32,
+    }
+
+    /// The shared value crossed `auto_pause_at`, so `inc` and `dec` are paused until the owner resumes them.
+    #[ink(event)]
+    pub struct AutoPaused {
+        value: i32,
+    }
+
+    /// Every way a mutating message can fail. All of them return `Result<_, Error>`.
+    #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub enum Error {
+        /// A personal value would drop below the configured `personal_floor`.
+        FloorViolated,
+        /// A mutating message was entered again before the previous call finished.
+        Reentrancy,
+        /// An increment was smaller than the configured `min_increment`.
+        IncrementTooSmall,
+        /// A value would go above `i32::MAX`.
+        Overflow,
+        /// A value would go below `i32::MIN`.
+        Underflow,
+        /// The message is reserved to the account that deployed the contract.
+        NotOwner,
+        /// The owner paused personal values, or the shared value was auto-paused.
+        Paused,
+        /// `inc_idempotent` was already applied with this key.
+        DuplicateKey,
+        /// The contract was wound down and no longer accepts changes.
+        Finalized,
+        /// `inc_mine` would add more than `daily_quota` within one quota window.
+        QuotaExceeded,
+        /// The `Config` limits contradict each other.
+        InvalidConfig,
+        /// `cas_mine` expected a personal value the caller no longer holds.
+        StaleRead,
+        /// `inc_by_oracle` got a report not signed by the contract's oracle.
+        BadOracleSig,
+        /// A personal value would be moved to the all-zero account, which nobody controls.
+        ZeroAccount,
+        /// A reputation weight above `i32::MAX`, the most an increment can be multiplied by.
+        CapExceeded,
+        /// `transfer_mine` would move more than the caller's personal value holds.
+        InsufficientBalance,
+        /// `contribute_all_mine` would fold a negative personal value into a shared value too
+        /// small to cover it.
+        InsufficientShared,
+        /// The contract was not set up for the message, e.g. `inc_by_oracle` without an oracle.
+        NotAllowed,
+    }
+
+    /// A single operation applied by `multicall`.
+    #[derive(Debug, PartialEq, Eq, Clone, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub enum Op {
+        Inc(i32),
+        Dec(i32),
+        IncMine(i32),
+    }
+
+    /// The state a batch of operations would leave behind.
+    struct Applied {
+        value: i32,
+        mine: Option<i32>,
+        quota: Option<(Timestamp, i32)>,
+        logged: Vec<i32>,
+        /// Personal operations in the batch, each counted in `op_count` like a single call.
+        personal_ops: u64,
+    }
+
+    /// How `inc` transforms the amount it is asked to add, before reputation weighting.
+    #[derive(
+        Debug,
+        PartialEq,
+        Eq,
+        Clone,
+        Copy,
+        scale::Encode,
+        scale::Decode,
+        ink_storage::traits::SpreadLayout,
+        ink_storage::traits::PackedLayout,
+    )]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout)
+    )]
+    pub enum IncrementPolicy {
+        /// Adds the amount as is.
+        Linear,
+        /// Adds twice the amount.
+        Doubling,
+        /// Adds the amount, but never more than the cap.
+        Capped(i32),
+    }
+
+    /// The limits the contract was configured with at construction.
+    #[derive(Debug, PartialEq, Eq, Clone, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, serde::Serialize, serde::Deserialize)
+    )]
+    pub struct Config {
+        pub personal_floor: i32,
+        pub min_increment: i32,
+        /// Most a caller's `inc_mine` calls may add up to within `quota_window` milliseconds.
+        pub daily_quota: i32,
+        pub quota_window: Timestamp,
+        /// Shared value at which `inc` and `dec` pause themselves, if any.
+        pub auto_pause_at: Option<i32>,
+    }
+
+    /// The contract's state at a glance, serializable to JSON for off-chain tooling.
+    #[derive(Debug, PartialEq, Eq, Clone, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, serde::Serialize, serde::Deserialize)
+    )]
+    pub struct Snapshot {
+        pub value: i32,
+        pub config: Config,
+        pub participants: u32,
+        pub inc_count: u64,
+        pub mine_paused: bool,
+        pub paused: bool,
+        pub finalized: bool,
+        pub version: u16,
+    }
+
+    impl Config {
+        /// Rejects limits no `inc_mine` could ever satisfy.
+        pub fn validate(&self) -> Result<(), Error> {
+            if self.quota_window == 0
+                || self.daily_quota < 0
+                || self.min_increment > self.daily_quota
+            {
+                return Err(Error::InvalidConfig)
+            }
+            Ok(())
+        }
+    }
+
+    impl Default for Config {
+        fn default() -> Self {
+            Self {
+                personal_floor: 0,
+                min_increment: 1,
+                daily_quota: i32::MAX,
+                quota_window: ONE_DAY,
+                auto_pause_at: None,
+            }
+        }
     }
 
     impl Incrementer {
         #[ink(constructor)]
         pub fn new(init_value: i32) -> Self {
+            Self::with_config(init_value, Config::default())
+        }
+
+        #[ink(constructor)]
+        pub fn with_personal_floor(init_value: i32, personal_floor: i32) -> Self {
+            Self::with_config(init_value, Config {
+                personal_floor,
+                ..Config::default()
+            })
+        }
+
+        /// Reverts the deployment if `config` is contradictory, as constructors can't return a
+        /// `Result`.
+        #[ink(constructor)]
+        pub fn with_config(init_value: i32, config: Config) -> Self {
+            config.validate().expect("contradictory config");
             Self {
                 value: init_value,
                 my_value: ink_storage::collections::HashMap::new(),
+                named: ink_storage::collections::HashMap::new(),
+                personal_floor: config.personal_floor,
+                min_increment: config.min_increment,
+                locked: false,
+                event_log: ink_storage::collections::Vec::new(),
+                inc_count: 0,
+                owner: Self::env().caller(),
+                mine_paused: false,
+                version: STORAGE_VERSION,
+                applied_keys: ink_storage::collections::HashMap::new(),
+                finalized: false,
+                reputation: ink_storage::collections::HashMap::new(),
+                daily_quota: config.daily_quota,
+                quota_window: config.quota_window,
+                quota_used: ink_storage::collections::HashMap::new(),
+                auto_pause_at: config.auto_pause_at,
+                paused: false,
+                last_inc_block: None,
+                policy: IncrementPolicy::Linear,
+                high_water_mark: init_value,
+                oracle: None,
+                oracle_nonce: 0,
+                op_count: ink_storage::collections::HashMap::new(),
+                event_log_head: 0,
             }
         }
 
+        /// Like `with_config` starting from zero, but returns `Error::InvalidConfig` for a
+        /// contradictory `config` instead of panicking.
+        pub fn new_checked(config: Config) -> Result<Self, Error> {
+            config.validate()?;
+            Ok(Self::with_config(0, config))
+        }
+
+        /// Seeds personal values from `balances`, like a genesis allocation.
         #[ink(constructor)]
-        pub fn default() -> Self {
-            Self {
-                value: 0,
-                my_value: Default::default(),
+        pub fn with_balances(init_value: i32, balances: Vec<(AccountId, i32)>) -> Self {
+            let mut contract = Self::new(init_value);
+            for (account, value) in balances {
+                contract.my_value.insert(account, value);
             }
+            contract
+        }
+
+        /// Trusts `oracle`, an ed25519 key, to report increments through `inc_by_oracle`.
+        #[ink(constructor)]
+        pub fn with_oracle(init_value: i32, oracle: AccountId) -> Self {
+            let mut contract = Self::new(init_value);
+            contract.oracle = Some(oracle);
+            contract
+        }
+
+        #[ink(constructor)]
+        pub fn default() -> Self {
+            Self::new(0)
         }
 
         #[ink(message)]
@@ -34,8 +319,172 @@ mod incrementer {
         }
 
         #[ink(message)]
-        pub fn inc(&mut self, add_value: i32) {
-            self.value += add_value;
+        pub fn snapshot(&self) -> Snapshot {
+            Snapshot {
+                value: self.value,
+                config: self.config(),
+                participants: self.participants(),
+                inc_count: self.inc_count,
+                mine_paused: self.mine_paused,
+                paused: self.paused,
+                finalized: self.finalized,
+                version: self.version,
+            }
+        }
+
+        /// What `value` would become after growing by `percent` for `periods` periods, rounding
+        /// toward zero each period.
+        #[ink(message)]
+        pub fn project(&self, percent: u32, periods: u32) -> Result<i32, Error> {
+            let mut projected = self.value as i64;
+            for _ in 0..periods {
+                let growth = projected
+                    .checked_mul(percent as i64)
+                    .map(|scaled| scaled / 100)
+                    .ok_or(Error::Overflow)?;
+                if growth == 0 {
+                    break
+                }
+                projected = projected.checked_add(growth).ok_or(Error::Overflow)?;
+                if projected > i32::MAX as i64 {
+                    return Err(Error::Overflow)
+                }
+                if projected < i32::MIN as i64 {
+                    return Err(Error::Underflow)
+                }
+            }
+            Ok(projected as i32)
+        }
+
+        /// The storage layout version this instance was written with.
+        #[ink(message)]
+        pub fn version(&self) -> u16 {
+            self.version
+        }
+
+        #[ink(message)]
+        pub fn config(&self) -> Config {
+            Config {
+                personal_floor: self.personal_floor,
+                min_increment: self.min_increment,
+                daily_quota: self.daily_quota,
+                quota_window: self.quota_window,
+                auto_pause_at: self.auto_pause_at,
+            }
+        }
+
+        /// Adds `add_value` scaled by the caller's reputation weight.
+        #[ink(message)]
+        pub fn inc(&mut self, add_value: i32) -> Result<(i32, i32), Error> {
+            self.ensure_not_finalized()?;
+            self.ensure_not_paused()?;
+            self.ensure_min_increment(add_value)?;
+            let add_value = self.apply_policy(add_value)?;
+            let weighted = self.weighted(&self.env().caller(), add_value)?;
+            let new_value = self.value.checked_add(weighted).ok_or(Error::Overflow)?;
+            self.enter()?;
+            let old_value = self.value;
+            self.value = new_value;
+            self.record_inc(old_value);
+            self.exit();
+            Ok((old_value, self.value))
+        }
+
+        /// Applies `inc` at most once per `key`, so relayers can safely retry. Returns the new value.
+        #[ink(message)]
+        pub fn inc_idempotent(&mut self, add_value: i32, key: Hash) -> Result<i32, Error> {
+            if self.applied_keys.contains_key(&key) {
+                return Err(Error::DuplicateKey)
+            }
+            let (_, new_value) = self.inc(add_value)?;
+            self.applied_keys.insert(key, ());
+            Ok(new_value)
+        }
+
+        #[ink(message)]
+        pub fn dec(&mut self, sub_value: i32) -> Result<(i32, i32), Error> {
+            self.ensure_not_finalized()?;
+            self.ensure_not_paused()?;
+            self.enter()?;
+            let old_value = self.value;
+            let new_value = Self::checked_dec(old_value, sub_value);
+            if let Ok(new_value) = new_value {
+                self.value = new_value;
+            }
+            self.exit();
+            new_value.map(|new_value| (old_value, new_value))
+        }
+
+        /// Applies `ops` in order, or none of them if any fails.
+        #[ink(message)]
+        pub fn multicall(&mut self, ops: Vec<Op>) -> Result<(), Error> {
+            self.ensure_not_finalized()?;
+            self.enter()?;
+            let caller = self.env().caller();
+            let applied = self.apply_ops(&caller, &ops);
+            if let Ok(applied) = &applied {
+                let old_value = self.value;
+                self.value = applied.value;
+                self.raise_high_water();
+                self.auto_pause(old_value);
+                if let Some(mine) = applied.mine {
+                    self.my_value.insert(caller, mine);
+                    self.count_ops(caller, applied.personal_ops);
+                }
+                if let Some(quota) = applied.quota {
+                    self.quota_used.insert(caller, quota);
+                }
+                for new_value in &applied.logged {
+                    self.inc_count = self.inc_count.saturating_add(1);
+                    self.last_inc_block = Some(self.env().block_number());
+                    self.log_event(*new_value, caller);
+                }
+                self.env().emit_event(BatchApplied {
+                    ops: ops.len() as u32,
+                    final_value: self.value,
+                });
+            }
+            self.exit();
+            applied.map(|_| ())
+        }
+
+        /// The shared value `multicall(ops)` would leave behind for the caller, or the error it
+        /// would fail with. Nothing is written.
+        #[ink(message)]
+        pub fn simulate(&self, ops: Vec<Op>) -> Result<i32, Error> {
+            self.ensure_not_finalized()?;
+            self.apply_ops(&self.env().caller(), &ops)
+                .map(|applied| applied.value)
+        }
+
+        /// Blocks elapsed since the latest shared increment, or `BlockNumber::MAX` if there was none.
+        #[ink(message)]
+        pub fn blocks_since_last_inc(&self) -> BlockNumber {
+            match self.last_inc_block {
+                Some(block) => self.env().block_number().saturating_sub(block),
+                None => BlockNumber::MAX,
+            }
+        }
+
+        /// Highest value the shared counter ever reached, even if it has come down since.
+        #[ink(message)]
+        pub fn high_water(&self) -> i32 {
+            self.high_water_mark
+        }
+
+        /// How many increments were applied, whatever their amounts.
+        #[ink(message)]
+        pub fn inc_count(&self) -> u64 {
+            self.inc_count
+        }
+
+        #[ink(message)]
+        pub fn recent_events(&self) -> Vec<(i32, AccountId)> {
+            let len = self.event_log.len();
+            (0..len)
+                .filter_map(|offset| self.event_log.get((self.event_log_head + offset) % len))
+                .cloned()
+                .collect()
         }
 
         #[ink(message)]
@@ -45,15 +494,537 @@ mod incrementer {
         }
 
         #[ink(message)]
-        pub fn inc_mine(&mut self, add_value: i32) {
+        pub fn get_for(&self, account: AccountId) -> i32 {
+            self.my_value_or_zero(&account)
+        }
+
+        /// The shared value plus every personal value, wide enough that it can't overflow.
+        #[ink(message)]
+        pub fn grand_total(&self) -> i128 {
+            self.my_value
+                .values()
+                .fold(self.value as i128, |total, value| total + *value as i128)
+        }
+
+        /// Number of accounts holding a personal value.
+        #[ink(message)]
+        pub fn participants(&self) -> u32 {
+            self.my_value.len()
+        }
+
+        /// Whether the caller holds a personal value, even one that is zero.
+        #[ink(message)]
+        pub fn has_participated(&self) -> bool {
+            let caller = self.env().caller();
+            self.my_value.get(&caller).is_some()
+        }
+
+        /// Pauses or resumes `inc_mine` and `dec_mine`, leaving the shared value untouched.
+        #[ink(message)]
+        pub fn set_mine_paused(&mut self, paused: bool) -> Result<(), Error> {
+            self.ensure_owner()?;
+            self.mine_paused = paused;
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn policy(&self) -> IncrementPolicy {
+            self.policy
+        }
+
+        #[ink(message)]
+        pub fn set_policy(&mut self, policy: IncrementPolicy) -> Result<(), Error> {
+            self.ensure_owner()?;
+            self.policy = policy;
+            Ok(())
+        }
+
+        /// Pauses or resumes `inc` and `dec`, e.g. after an auto-pause.
+        #[ink(message)]
+        pub fn set_paused(&mut self, paused: bool) -> Result<(), Error> {
+            self.ensure_owner()?;
+            self.paused = paused;
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn inc_mine(&mut self, add_value: i32) -> Result<(), Error> {
+            self.ensure_not_finalized()?;
+            self.ensure_mine_not_paused()?;
+            self.ensure_min_increment(add_value)?;
+            let caller = self.env().caller();
+            let caller_value = self.my_value_or_zero(&caller);
+            let new_value = caller_value.checked_add(add_value).ok_or(Error::Overflow)?;
+            let quota = self.quota_after(&caller, None, add_value)?;
+            self.enter()?;
+            self.my_value.insert(caller, new_value);
+            self.quota_used.insert(caller, quota);
+            self.count_ops(caller, 1);
+            self.exit();
+            Ok(())
+        }
+
+        /// `inc_mine`, but only if the caller's personal value is still `expected`. Returns the new value.
+        #[ink(message)]
+        pub fn cas_mine(&mut self, expected: i32, add: i32) -> Result<i32, Error> {
+            if self.get_mine() != expected {
+                return Err(Error::StaleRead)
+            }
+            self.inc_mine(add)?;
+            Ok(self.get_mine())
+        }
+
+        /// Adds `reported` if the oracle signed it, as the SCALE encoding of
+        /// `(reported, oracle_nonce())`. The nonce then moves on, so each report applies once.
+        #[ink(message)]
+        pub fn inc_by_oracle(
+            &mut self,
+            reported: i32,
+            oracle_sig: [u8; 64],
+            oracle_key: AccountId,
+        ) -> Result<(), Error> {
+            self.ensure_not_finalized()?;
+            self.ensure_not_paused()?;
+            if self.oracle.is_none() {
+                return Err(Error::NotAllowed)
+            }
+            if self.oracle != Some(oracle_key)
+                || !Self::signed_by_oracle(&oracle_key, &(reported, self.oracle_nonce), &oracle_sig)
+            {
+                return Err(Error::BadOracleSig)
+            }
+            let new_value = self.value.checked_add(reported).ok_or(Error::Overflow)?;
+            self.enter()?;
+            let old_value = self.value;
+            self.value = new_value;
+            self.oracle_nonce = self.oracle_nonce.saturating_add(1);
+            self.record_inc(old_value);
+            self.exit();
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn oracle_nonce(&self) -> u64 {
+            self.oracle_nonce
+        }
+
+        /// 1-based rank of the caller by personal value, ties going to the lower `AccountId`.
+        /// Callers without a personal value rank last.
+        #[ink(message)]
+        pub fn my_rank(&self) -> u32 {
+            let caller = self.env().caller();
+            match self.my_value.get(&caller) {
+                Some(mine) => {
+                    let ahead = self
+                        .my_value
+                        .iter()
+                        .filter(|(account, value)| {
+                            *value > mine || (*value == mine && *account < &caller)
+                        })
+                        .count();
+                    ahead as u32 + 1
+                }
+                None => self.my_value.len() + 1,
+            }
+        }
+
+        /// The `n` accounts with the highest personal values, ties going to the lower `AccountId`.
+        #[ink(message)]
+        pub fn top_n(&self, n: u32) -> Vec<(AccountId, i32)> {
+            let mut ranked: Vec<(AccountId, i32)> = self
+                .my_value
+                .iter()
+                .map(|(account, value)| (*account, *value))
+                .collect();
+            ranked.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+            ranked.truncate(n as usize);
+            ranked
+        }
+
+        #[ink(message)]
+        pub fn dec_mine(&mut self, sub_value: i32) -> Result<(), Error> {
+            self.ensure_not_finalized()?;
+            self.ensure_mine_not_paused()?;
             let caller = self.env().caller();
             let caller_value = self.my_value_or_zero(&caller);
-            self.my_value.insert(caller, caller_value + add_value);
+            let new_value = Self::checked_dec(caller_value, sub_value)?;
+            if new_value < self.personal_floor {
+                return Err(Error::FloorViolated)
+            }
+            self.my_value.insert(caller, new_value);
+            self.count_ops(caller, 1);
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn transfer_mine(&mut self, to: AccountId, value: i32) -> Result<(), Error> {
+            self.ensure_not_finalized()?;
+            Self::ensure_not_zero(&to)?;
+            let caller = self.env().caller();
+            let caller_value = self.my_value_or_zero(&caller);
+            if value > 0 && value > caller_value {
+                return Err(Error::InsufficientBalance)
+            }
+            let caller_value = Self::checked_dec(caller_value, value)?;
+            if caller_value < self.personal_floor {
+                return Err(Error::FloorViolated)
+            }
+            let to_value = self
+                .my_value_or_zero(&to)
+                .checked_add(value)
+                .ok_or(Error::Overflow)?;
+            self.my_value.insert(caller, caller_value);
+            self.my_value.insert(to, to_value);
+            self.count_ops(caller, 1);
+            Ok(())
+        }
+
+        /// Personal operations the caller has applied since the counts were last reset.
+        #[ink(message)]
+        pub fn my_op_count(&self) -> u64 {
+            let caller = self.env().caller();
+            self.op_count.get(&caller).copied().unwrap_or(0)
+        }
+
+        /// Zeroes every account's `my_op_count`.
+        #[ink(message)]
+        pub fn reset_op_counts(&mut self) -> Result<(), Error> {
+            self.ensure_owner()?;
+            let accounts: Vec<AccountId> = self.op_count.keys().cloned().collect();
+            for account in accounts {
+                self.op_count.take(&account);
+            }
+            Ok(())
+        }
+
+        /// Moves the caller's whole personal value into the shared one and returns the new total.
+        #[ink(message)]
+        pub fn contribute_all_mine(&mut self) -> Result<i32, Error> {
+            self.ensure_not_finalized()?;
+            self.ensure_not_paused()?;
+            let caller = self.env().caller();
+            if 0 < self.personal_floor {
+                return Err(Error::FloorViolated)
+            }
+            let mine = self.my_value_or_zero(&caller);
+            if mine < 0 && (self.value as i64) + (mine as i64) < 0 {
+                return Err(Error::InsufficientShared)
+            }
+            let new_value = self.value.checked_add(mine).ok_or(Error::Overflow)?;
+            self.enter()?;
+            let old_value = self.value;
+            self.value = new_value;
+            self.raise_high_water();
+            self.auto_pause(old_value);
+            self.my_value.insert(caller, 0);
+            self.count_ops(caller, 1);
+            self.exit();
+            Ok(new_value)
+        }
+
+        /// Sets the weight `account`'s shared increments are multiplied by. Unset accounts weigh 1.
+        #[ink(message)]
+        pub fn set_reputation(&mut self, account: AccountId, weight: u32) -> Result<(), Error> {
+            self.ensure_owner()?;
+            if weight > i32::MAX as u32 {
+                return Err(Error::CapExceeded)
+            }
+            self.reputation.insert(account, weight);
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn reputation_of(&self, account: AccountId) -> u32 {
+            self.reputation.get(&account).copied().unwrap_or(1)
+        }
+
+        /// Exchanges the personal values of `a` and `b`, to correct a misattribution.
+        #[ink(message)]
+        pub fn swap_mine(&mut self, a: AccountId, b: AccountId) -> Result<(), Error> {
+            self.ensure_owner()?;
+            self.ensure_not_finalized()?;
+            Self::ensure_not_zero(&a)?;
+            Self::ensure_not_zero(&b)?;
+            let a_value = self.my_value.take(&a);
+            let b_value = self.my_value.take(&b);
+            if let Some(b_value) = b_value {
+                self.my_value.insert(a, b_value);
+            }
+            if let Some(a_value) = a_value {
+                self.my_value.insert(b, a_value);
+            }
+            self.env().emit_event(MineSwapped { a, b });
+            Ok(())
+        }
+
+        /// Zeroes the shared value. Personal values and `inc_count` are kept.
+        #[ink(message)]
+        pub fn reset(&mut self) -> Result<(), Error> {
+            self.ensure_owner()?;
+            self.ensure_not_finalized()?;
+            let previous = self.value;
+            self.value = 0;
+            self.env().emit_event(Reset {
+                previous,
+                inc_count_at_reset: self.inc_count,
+            });
+            Ok(())
+        }
+
+        /// Moves every personal value into the shared one and stops accepting changes for good.
+        #[ink(message)]
+        pub fn wind_down(&mut self) -> Result<(), Error> {
+            self.ensure_owner()?;
+            self.ensure_not_finalized()?;
+            let mut total_absorbed: i32 = 0;
+            for (_, value) in self.my_value.iter() {
+                total_absorbed = total_absorbed.checked_add(*value).ok_or(Error::Overflow)?;
+            }
+            let new_value = self.value.checked_add(total_absorbed).ok_or(Error::Overflow)?;
+            let accounts: Vec<AccountId> = self.my_value.keys().cloned().collect();
+            for account in accounts {
+                self.my_value.take(&account);
+            }
+            self.value = new_value;
+            self.raise_high_water();
+            self.finalized = true;
+            self.env().emit_event(WoundDown { total_absorbed });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn get_named(&self, name: Hash) -> i32 {
+            self.named_or_zero(&name)
+        }
+
+        #[ink(message)]
+        pub fn get_named_batch(&self, names: Vec<Hash>) -> Vec<i32> {
+            names.iter().map(|name| self.named_or_zero(name)).collect()
+        }
+
+        #[ink(message)]
+        pub fn inc_named(&mut self, name: Hash, add_value: i32) -> Result<(), Error> {
+            self.ensure_not_finalized()?;
+            let named_value = self
+                .named_or_zero(&name)
+                .checked_add(add_value)
+                .ok_or(Error::Overflow)?;
+            self.named.insert(name, named_value);
+            Ok(())
+        }
+
+        /// Works out the effect of `ops` without writing anything to storage.
+        fn apply_ops(&self, caller: &AccountId, ops: &[Op]) -> Result<Applied, Error> {
+            let mut applied = Applied {
+                value: self.value,
+                mine: None,
+                quota: None,
+                logged: Vec::new(),
+                personal_ops: 0,
+            };
+            for op in ops {
+                match *op {
+                    Op::Inc(add_value) => {
+                        self.ensure_not_paused()?;
+                        self.ensure_min_increment(add_value)?;
+                        let add_value = self.apply_policy(add_value)?;
+                        let weighted = self.weighted(caller, add_value)?;
+                        applied.value =
+                            applied.value.checked_add(weighted).ok_or(Error::Overflow)?;
+                        applied.logged.push(applied.value);
+                    }
+                    Op::Dec(sub_value) => {
+                        self.ensure_not_paused()?;
+                        applied.value = Self::checked_dec(applied.value, sub_value)?;
+                    }
+                    Op::IncMine(add_value) => {
+                        self.ensure_mine_not_paused()?;
+                        self.ensure_min_increment(add_value)?;
+                        let mine = applied.mine.unwrap_or_else(|| self.my_value_or_zero(caller));
+                        applied.mine = Some(mine.checked_add(add_value).ok_or(Error::Overflow)?);
+                        applied.quota = Some(self.quota_after(caller, applied.quota, add_value)?);
+                        applied.personal_ops += 1;
+                    }
+                }
+            }
+            Ok(applied)
+        }
+
+        fn apply_policy(&self, add_value: i32) -> Result<i32, Error> {
+            match self.policy {
+                IncrementPolicy::Linear => Ok(add_value),
+                IncrementPolicy::Doubling => add_value.checked_mul(2).ok_or(if add_value < 0 {
+                    Error::Underflow
+                } else {
+                    Error::Overflow
+                }),
+                IncrementPolicy::Capped(cap) => Ok(add_value.min(cap)),
+            }
+        }
+
+        fn weighted(&self, caller: &AccountId, add_value: i32) -> Result<i32, Error> {
+            let weight = self.reputation_of(*caller);
+            if weight > i32::MAX as u32 {
+                return Err(Error::Overflow)
+            }
+            add_value.checked_mul(weight as i32).ok_or(if add_value < 0 {
+                Error::Underflow
+            } else {
+                Error::Overflow
+            })
+        }
+
+        /// Subtracting a negative value (down to `i32::MIN`) moves up, so it can overflow too.
+        fn checked_dec(value: i32, sub_value: i32) -> Result<i32, Error> {
+            value.checked_sub(sub_value).ok_or(if sub_value < 0 {
+                Error::Overflow
+            } else {
+                Error::Underflow
+            })
+        }
+
+        /// Appends to the bounded event log. Once it is full the oldest entry is overwritten in
+        /// place, so logging costs one storage write however long the log is.
+        fn log_event(&mut self, new_value: i32, caller: AccountId) {
+            if self.event_log.len() < EVENT_LOG_CAPACITY {
+                self.event_log.push((new_value, caller));
+                return
+            }
+            let head = self.event_log_head;
+            self.event_log
+                .set(head, (new_value, caller))
+                .expect("the head is in bounds of a full log");
+            self.event_log_head = (head + 1) % EVENT_LOG_CAPACITY;
+        }
+
+        /// The caller's quota window once `add_value` is added to it, starting a new window when
+        /// `quota_window` has elapsed. `current` overrides the stored window, for batches.
+        fn quota_after(
+            &self,
+            caller: &AccountId,
+            current: Option<(Timestamp, i32)>,
+            add_value: i32,
+        ) -> Result<(Timestamp, i32), Error> {
+            let now = self.env().block_timestamp();
+            let (start, used) = match current.or_else(|| self.quota_used.get(caller).copied()) {
+                Some((start, used)) if now < start.saturating_add(self.quota_window) => (start, used),
+                _ => (now, 0),
+            };
+            let used = used.checked_add(add_value).ok_or(Error::Overflow)?;
+            if used > self.daily_quota {
+                return Err(Error::QuotaExceeded)
+            }
+            Ok((start, used))
+        }
+
+        fn ensure_owner(&self) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::NotOwner)
+            }
+            Ok(())
+        }
+
+        fn ensure_not_finalized(&self) -> Result<(), Error> {
+            if self.finalized {
+                return Err(Error::Finalized)
+            }
+            Ok(())
+        }
+
+        fn ensure_not_paused(&self) -> Result<(), Error> {
+            if self.paused {
+                return Err(Error::Paused)
+            }
+            Ok(())
+        }
+
+        fn signed_by_oracle(
+            oracle_key: &AccountId,
+            report: &impl scale::Encode,
+            signature: &[u8; 64],
+        ) -> bool {
+            let public = ed25519_dalek::PublicKey::from_bytes(&scale::Encode::encode(oracle_key));
+            let signature = ed25519_dalek::Signature::from_bytes(signature);
+            match (public, signature) {
+                (Ok(public), Ok(signature)) => {
+                    public.verify(&report.encode(), &signature).is_ok()
+                }
+                _ => false,
+            }
+        }
+
+        /// Bookkeeping after a single shared increment from `old_value`: the count, block, event
+        /// log, high-water mark and auto-pause.
+        fn record_inc(&mut self, old_value: i32) {
+            self.inc_count = self.inc_count.saturating_add(1);
+            self.last_inc_block = Some(self.env().block_number());
+            self.log_event(self.value, self.env().caller());
+            self.raise_high_water();
+            self.auto_pause(old_value);
+        }
+
+        fn raise_high_water(&mut self) {
+            if self.value > self.high_water_mark {
+                self.high_water_mark = self.value;
+            }
+        }
+
+        /// Pauses the shared value if moving from `old_value` to `value` crossed `auto_pause_at`.
+        fn auto_pause(&mut self, old_value: i32) {
+            if let Some(threshold) = self.auto_pause_at {
+                if old_value < threshold && self.value >= threshold {
+                    self.paused = true;
+                    self.env().emit_event(AutoPaused { value: self.value });
+                }
+            }
+        }
+
+        fn count_ops(&mut self, caller: AccountId, ops: u64) {
+            let count = self.op_count.get(&caller).copied().unwrap_or(0);
+            self.op_count.insert(caller, count.saturating_add(ops));
+        }
+
+        fn ensure_not_zero(account: &AccountId) -> Result<(), Error> {
+            if *account == AccountId::from([0x0; 32]) {
+                return Err(Error::ZeroAccount)
+            }
+            Ok(())
+        }
+
+        fn ensure_mine_not_paused(&self) -> Result<(), Error> {
+            if self.mine_paused {
+                return Err(Error::Paused)
+            }
+            Ok(())
+        }
+
+        fn ensure_min_increment(&self, add_value: i32) -> Result<(), Error> {
+            if add_value < self.min_increment {
+                return Err(Error::IncrementTooSmall)
+            }
+            Ok(())
+        }
+
+        /// Guards against a cross-contract call re-entering a mutating message.
+        fn enter(&mut self) -> Result<(), Error> {
+            if self.locked {
+                return Err(Error::Reentrancy)
+            }
+            self.locked = true;
+            Ok(())
+        }
+
+        fn exit(&mut self) {
+            self.locked = false;
         }
 
         fn my_value_or_zero(&self, of: &AccountId) -> i32 {
             *self.my_value.get(of).unwrap_or(&0)
         }
+
+        fn named_or_zero(&self, name: &Hash) -> i32 {
+            *self.named.get(name).unwrap_or(&0)
+        }
     }
 
     #[cfg(test)]
@@ -63,21 +1034,32 @@ mod incrementer {
         // Alias `ink_lang` so we can use `ink::test`.
         use ink_lang as ink;
 
-        #[test]
+        type Event = <Incrementer as ::ink_lang::BaseEvent>::Type;
+
+        fn set_caller(caller: AccountId) {
+            ink_env::test::push_execution_context::<ink_env::DefaultEnvironment>(
+                caller,
+                AccountId::from([0xF; 32]),
+                1_000_000,
+                0,
+                ink_env::test::CallData::new(ink_env::call::Selector::new([0x00; 4])),
+            );
+        }
+
+        #[ink::test]
         fn default_works() {
             let increment = Incrementer::default();
             assert_eq!(increment.get(), 0);
         }
 
-        #[test]
+        #[ink::test]
         fn it_works() {
             let mut increment = Incrementer::new(42);
             assert_eq!(increment.get(), 42);
-            increment.inc(10);
+            assert_eq!(increment.inc(10), Ok((42, 52)));
             assert_eq!(increment.get(), 52);
-            increment.inc(7);
+            assert_eq!(increment.inc(7), Ok((52, 59)));
             assert_eq!(increment.get(), 59);
-
         }
 
         #[ink::test]
@@ -85,10 +1067,686 @@ mod incrementer {
             let mut contract = Incrementer::new(11);
             assert_eq!(contract.get(), 11);
             assert_eq!(contract.get_mine(), 0);
-            contract.inc_mine(5);
+            assert_eq!(contract.inc_mine(5), Ok(()));
             assert_eq!(contract.get_mine(), 5);
-            contract.inc_mine(10);
+            assert_eq!(contract.inc_mine(10), Ok(()));
             assert_eq!(contract.get_mine(), 15);
         }
+
+        #[ink::test]
+        fn personal_floor_works() {
+            let mut contract = Incrementer::with_personal_floor(0, 0);
+            assert_eq!(contract.inc_mine(5), Ok(()));
+            assert_eq!(contract.dec_mine(6), Err(Error::FloorViolated));
+            assert_eq!(contract.get_mine(), 5);
+            assert_eq!(
+                contract.transfer_mine(AccountId::from([0x2; 32]), 6),
+                Err(Error::InsufficientBalance)
+            );
+            assert_eq!(contract.get_mine(), 5);
+            assert_eq!(contract.dec_mine(5), Ok(()));
+            assert_eq!(contract.get_mine(), 0);
+        }
+
+        #[ink::test]
+        fn config_works() {
+            let contract = Incrementer::with_personal_floor(7, -5);
+            assert_eq!(contract.config(), Config {
+                personal_floor: -5,
+                ..Config::default()
+            });
+            assert_eq!(Incrementer::default().config(), Config::default());
+        }
+
+        #[ink::test]
+        fn min_increment_works() {
+            let mut contract = Incrementer::with_config(0, Config {
+                min_increment: 5,
+                ..Config::default()
+            });
+            assert_eq!(contract.inc(3), Err(Error::IncrementTooSmall));
+            assert_eq!(contract.inc_mine(3), Err(Error::IncrementTooSmall));
+            assert_eq!(contract.get(), 0);
+            assert_eq!(contract.inc(5), Ok((0, 5)));
+            assert_eq!(contract.inc_mine(5), Ok(()));
+            assert_eq!(contract.get_mine(), 5);
+        }
+
+        #[ink::test]
+        fn reentrancy_guard_works() {
+            let mut contract = Incrementer::new(0);
+            assert!(!contract.locked);
+            assert_eq!(contract.inc(1), Ok((0, 1)));
+            assert!(!contract.locked);
+
+            // simulate a cross-contract call re-entering while a message is still running
+            contract.enter().unwrap();
+            assert_eq!(contract.inc(1), Err(Error::Reentrancy));
+            assert_eq!(contract.inc_mine(1), Err(Error::Reentrancy));
+            contract.exit();
+
+            assert_eq!(contract.get(), 1);
+            assert_eq!(contract.get_mine(), 0);
+        }
+
+        #[ink::test]
+        fn my_rank_works() {
+            let mut contract = Incrementer::new(0);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            let charlie = AccountId::from([0x3; 32]);
+            let django = AccountId::from([0x4; 32]);
+            for (account, value) in &[(alice, 5), (bob, 20), (charlie, 10)] {
+                set_caller(*account);
+                assert_eq!(contract.inc_mine(*value), Ok(()));
+            }
+
+            set_caller(bob);
+            assert_eq!(contract.my_rank(), 1);
+            set_caller(charlie);
+            assert_eq!(contract.my_rank(), 2);
+            set_caller(alice);
+            assert_eq!(contract.my_rank(), 3);
+            set_caller(django);
+            assert_eq!(contract.my_rank(), 4);
+        }
+
+        #[ink::test]
+        fn recent_events_works() {
+            let mut contract = Incrementer::new(0);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            set_caller(alice);
+            assert_eq!(contract.inc(1), Ok((0, 1)));
+            set_caller(bob);
+            assert_eq!(contract.inc(2), Ok((1, 3)));
+            assert_eq!(contract.recent_events(), vec![(1, alice), (3, bob)]);
+
+            for _ in 0..EVENT_LOG_CAPACITY {
+                assert!(contract.inc(1).is_ok());
+            }
+            let events = contract.recent_events();
+            assert_eq!(events.len() as u32, EVENT_LOG_CAPACITY);
+            assert_eq!(events.first(), Some(&(4, bob)));
+            assert_eq!(events.last(), Some(&(11, bob)));
+            // oldest first, even though the log wrapped around
+            let values: Vec<i32> = events.iter().map(|(value, _)| *value).collect();
+            assert_eq!(values, (4..=11).collect::<Vec<i32>>());
+        }
+
+        #[ink::test]
+        fn dec_works() {
+            let mut contract = Incrementer::new(10);
+            assert_eq!(contract.dec(4), Ok((10, 6)));
+            assert_eq!(contract.get(), 6);
+        }
+
+        #[ink::test]
+        fn dec_underflow_is_rejected() {
+            let mut contract = Incrementer::new(i32::MIN);
+            assert_eq!(contract.dec(1), Err(Error::Underflow));
+            assert_eq!(contract.get(), i32::MIN);
+
+            let mut contract = Incrementer::new(0);
+            assert_eq!(contract.dec(i32::MIN), Err(Error::Overflow));
+            assert_eq!(contract.get(), 0);
+        }
+
+        #[ink::test]
+        fn multicall_works() {
+            let mut contract = Incrementer::new(10);
+            assert_eq!(
+                contract.multicall(vec![Op::Inc(5), Op::Dec(3), Op::IncMine(4), Op::IncMine(2)]),
+                Ok(())
+            );
+            assert_eq!(contract.get(), 12);
+            assert_eq!(contract.get_mine(), 6);
+        }
+
+        #[ink::test]
+        fn multicall_reverts_on_overflow() {
+            let mut contract = Incrementer::new(i32::MAX - 10);
+            assert_eq!(
+                contract.multicall(vec![Op::IncMine(7), Op::Inc(5), Op::Inc(6)]),
+                Err(Error::Overflow)
+            );
+            assert_eq!(contract.get(), i32::MAX - 10);
+            assert_eq!(contract.get_mine(), 0);
+            assert!(contract.recent_events().is_empty());
+        }
+
+        type Trigger = fn(&mut Incrementer) -> Result<(), Error>;
+
+        #[ink::test]
+        fn errors_match_their_triggers() {
+            let cases: Vec<(Trigger, Error)> = vec![
+                (|c| c.inc(0).map(|_| ()), Error::IncrementTooSmall),
+                (|c| { c.inc(i32::MAX)?; c.inc(1).map(|_| ()) }, Error::Overflow),
+                (|c| c.dec(i32::MIN).map(|_| ()), Error::Overflow),
+                (|c| { c.dec(i32::MAX)?; c.dec(2).map(|_| ()) }, Error::Underflow),
+                (|c| { c.inc_mine(i32::MAX)?; c.inc_mine(1) }, Error::Overflow),
+                (|c| c.dec_mine(1), Error::FloorViolated),
+                (
+                    |c| {
+                        c.personal_floor = 1;
+                        c.inc_mine(1)?;
+                        c.transfer_mine(AccountId::from([0x2; 32]), 1)
+                    },
+                    Error::FloorViolated,
+                ),
+                (|c| c.transfer_mine(AccountId::from([0x2; 32]), 1), Error::InsufficientBalance),
+                (|c| c.transfer_mine(AccountId::from([0x0; 32]), 1), Error::ZeroAccount),
+                (
+                    |c| {
+                        c.personal_floor = -5;
+                        c.dec_mine(5)?;
+                        c.contribute_all_mine().map(|_| ())
+                    },
+                    Error::InsufficientShared,
+                ),
+                (
+                    |c| {
+                        c.inc_named(Hash::default(), i32::MAX)?;
+                        c.inc_named(Hash::default(), 1)
+                    },
+                    Error::Overflow,
+                ),
+                (|c| c.multicall(vec![Op::Dec(i32::MAX), Op::Dec(2)]), Error::Underflow),
+                (|c| { c.locked = true; c.inc(1).map(|_| ()) }, Error::Reentrancy),
+                (
+                    |c| { set_caller(AccountId::from([0x0; 32])); c.set_mine_paused(true) },
+                    Error::NotOwner,
+                ),
+                (|c| { c.set_mine_paused(true)?; c.inc_mine(1) }, Error::Paused),
+                (|c| { c.wind_down()?; c.inc(1).map(|_| ()) }, Error::Finalized),
+                (
+                    |c| {
+                        c.inc_idempotent(1, Hash::default())?;
+                        c.inc_idempotent(1, Hash::default()).map(|_| ())
+                    },
+                    Error::DuplicateKey,
+                ),
+                (|c| c.set_reputation(AccountId::from([0x2; 32]), u32::MAX), Error::CapExceeded),
+                (|c| c.inc_by_oracle(1, [0; 64], AccountId::from([0x2; 32])), Error::NotAllowed),
+            ];
+            for (trigger, expected) in cases {
+                let mut contract = Incrementer::new(0);
+                assert_eq!(trigger(&mut contract), Err(expected));
+            }
+        }
+
+        #[ink::test]
+        fn top_n_works() {
+            let mut contract = Incrementer::new(0);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            let charlie = AccountId::from([0x3; 32]);
+            let django = AccountId::from([0x4; 32]);
+            for (account, value) in &[(alice, 5), (bob, 20), (charlie, 10), (django, 20)] {
+                set_caller(*account);
+                assert_eq!(contract.inc_mine(*value), Ok(()));
+            }
+
+            assert_eq!(contract.top_n(2), vec![(bob, 20), (django, 20)]);
+            assert_eq!(contract.top_n(0), vec![]);
+            assert_eq!(contract.top_n(10).len(), 4);
+        }
+
+        #[ink::test]
+        fn mine_paused_works() {
+            let mut contract = Incrementer::new(0);
+            assert_eq!(contract.set_mine_paused(true), Ok(()));
+            assert_eq!(contract.inc_mine(1), Err(Error::Paused));
+            assert_eq!(contract.dec_mine(1), Err(Error::Paused));
+            assert_eq!(contract.inc(1), Ok((0, 1)));
+
+            assert_eq!(contract.set_mine_paused(false), Ok(()));
+            assert_eq!(contract.inc_mine(1), Ok(()));
+            assert_eq!(contract.inc(1), Ok((1, 2)));
+            assert_eq!(contract.get_mine(), 1);
+        }
+
+        #[ink::test]
+        fn version_works() {
+            // an upgrade changing the layout bumps STORAGE_VERSION and migrates from the old one
+            let contract = Incrementer::new(0);
+            assert_eq!(contract.version(), STORAGE_VERSION);
+        }
+
+        #[ink::test]
+        fn inc_idempotent_works() {
+            let mut contract = Incrementer::new(0);
+            let key = Hash::from([0x1; 32]);
+            assert_eq!(contract.inc_idempotent(5, key), Ok(5));
+            assert_eq!(contract.inc_idempotent(5, key), Err(Error::DuplicateKey));
+            assert_eq!(contract.get(), 5);
+            assert_eq!(contract.inc_idempotent(5, Hash::from([0x2; 32])), Ok(10));
+        }
+
+        #[ink::test]
+        fn wind_down_works() {
+            let mut contract = Incrementer::with_balances(
+                10,
+                vec![(AccountId::from([0x1; 32]), 7), (AccountId::from([0x2; 32]), 11)],
+            );
+            assert_eq!(contract.wind_down(), Ok(()));
+            assert_eq!(contract.get(), 28);
+            assert_eq!(contract.participants(), 0);
+            assert_eq!(ink_env::test::recorded_events().count(), 1);
+
+            assert_eq!(contract.inc(1), Err(Error::Finalized));
+            assert_eq!(contract.inc_mine(1), Err(Error::Finalized));
+            assert_eq!(contract.wind_down(), Err(Error::Finalized));
+            assert_eq!(contract.get(), 28);
+        }
+
+        #[cfg(feature = "std")]
+        #[ink::test]
+        fn snapshot_round_trips_through_json() {
+            let mut contract = Incrementer::with_balances(3, vec![(AccountId::from([0x1; 32]), 7)]);
+            assert_eq!(contract.inc(2), Ok((3, 5)));
+            let snapshot = contract.snapshot();
+            assert_eq!(snapshot.value, 5);
+            assert_eq!(snapshot.participants, 1);
+
+            let json = serde_json::to_string(&snapshot).unwrap();
+            assert_eq!(serde_json::from_str::<Snapshot>(&json).unwrap(), snapshot);
+        }
+
+        #[ink::test]
+        fn reset_works() {
+            let mut contract = Incrementer::new(1);
+            assert_eq!(contract.inc(2), Ok((1, 3)));
+            assert_eq!(contract.inc(4), Ok((3, 7)));
+            assert_eq!(contract.reset(), Ok(()));
+            assert_eq!(contract.get(), 0);
+
+            let events: Vec<_> = ink_env::test::recorded_events().collect();
+            assert_eq!(events.len(), 1);
+            match <Event as scale::Decode>::decode(&mut &events[0].data[..]) {
+                Ok(Event::Reset(Reset { previous, inc_count_at_reset })) => {
+                    assert_eq!(previous, 7);
+                    assert_eq!(inc_count_at_reset, 2);
+                }
+                _ => panic!("expected a Reset event"),
+            }
+
+            set_caller(AccountId::from([0x0; 32]));
+            assert_eq!(contract.reset(), Err(Error::NotOwner));
+        }
+
+        #[ink::test]
+        fn reputation_works() {
+            let mut contract = Incrementer::new(0);
+            let weighted = AccountId::from([0x2; 32]);
+            assert_eq!(contract.set_reputation(weighted, 3), Ok(()));
+
+            set_caller(weighted);
+            assert_eq!(contract.inc(10), Ok((0, 30)));
+            assert_eq!(contract.set_reputation(weighted, 1), Err(Error::NotOwner));
+
+            set_caller(AccountId::from([0x3; 32]));
+            assert_eq!(contract.reputation_of(AccountId::from([0x3; 32])), 1);
+            assert_eq!(contract.inc(10), Ok((30, 40)));
+        }
+
+        #[ink::test]
+        fn project_works() {
+            let contract = Incrementer::new(100);
+            assert_eq!(contract.project(10, 3), Ok(133));
+            assert_eq!(contract.project(10, 0), Ok(100));
+            assert_eq!(contract.project(100, 40), Err(Error::Overflow));
+            assert_eq!(Incrementer::new(-100).project(100, 40), Err(Error::Underflow));
+            assert_eq!(contract.get(), 100);
+        }
+
+        #[ink::test]
+        fn daily_quota_works() {
+            let mut contract = Incrementer::with_config(0, Config {
+                daily_quota: 10,
+                // the off-chain environment moves 5ms per block, so shrink the day to 3 blocks
+                quota_window: 15,
+                ..Config::default()
+            });
+            assert_eq!(contract.inc_mine(6), Ok(()));
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>().unwrap();
+            assert_eq!(contract.inc_mine(5), Err(Error::QuotaExceeded));
+            assert_eq!(contract.multicall(vec![Op::IncMine(2), Op::IncMine(3)]), Err(Error::QuotaExceeded));
+            assert_eq!(contract.inc_mine(4), Ok(()));
+            assert_eq!(contract.get_mine(), 10);
+
+            for _ in 0..2 {
+                ink_env::test::advance_block::<ink_env::DefaultEnvironment>().unwrap();
+            }
+            assert_eq!(contract.inc_mine(10), Ok(()));
+            assert_eq!(contract.get_mine(), 20);
+        }
+
+        #[ink::test]
+        fn swap_mine_works() {
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            let mut contract = Incrementer::with_balances(0, vec![(alice, 7), (bob, 11)]);
+            assert_eq!(contract.swap_mine(alice, bob), Ok(()));
+            assert_eq!(contract.get_for(alice), 11);
+            assert_eq!(contract.get_for(bob), 7);
+            assert_eq!(ink_env::test::recorded_events().count(), 1);
+
+            set_caller(bob);
+            assert_eq!(contract.swap_mine(alice, bob), Err(Error::NotOwner));
+            assert_eq!(contract.get_for(bob), 7);
+        }
+
+        #[ink::test]
+        fn grand_total_works() {
+            let mut contract = Incrementer::with_balances(
+                i32::MAX - 1,
+                vec![(AccountId::from([0x2; 32]), i32::MAX), (AccountId::from([0x3; 32]), 3)],
+            );
+            assert_eq!(contract.inc(1), Ok((i32::MAX - 1, i32::MAX)));
+            assert_eq!(contract.inc_mine(4), Ok(()));
+            assert_eq!(contract.grand_total(), 2 * i32::MAX as i128 + 7);
+        }
+
+        #[ink::test]
+        fn inc_count_works() {
+            let mut contract = Incrementer::new(0);
+            assert_eq!(contract.inc(1), Ok((0, 1)));
+            assert_eq!(contract.inc(5), Ok((1, 6)));
+            assert_eq!(contract.inc(20), Ok((6, 26)));
+            assert_eq!(contract.inc_count(), 3);
+            assert_eq!(contract.get(), 26);
+        }
+
+        #[ink::test]
+        fn contribute_all_mine_works() {
+            let mut contract = Incrementer::new(10);
+            assert_eq!(contract.inc_mine(15), Ok(()));
+
+            assert_eq!(contract.contribute_all_mine(), Ok(25));
+            assert_eq!(contract.get(), 25);
+            assert_eq!(contract.get_mine(), 0);
+        }
+
+        #[ink::test]
+        fn with_balances_works() {
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            let contract = Incrementer::with_balances(3, vec![(alice, 7), (bob, 11)]);
+            assert_eq!(contract.get(), 3);
+            assert_eq!(contract.get_for(alice), 7);
+            assert_eq!(contract.get_for(bob), 11);
+            assert_eq!(contract.get_for(AccountId::from([0x3; 32])), 0);
+            assert_eq!(contract.participants(), 2);
+        }
+
+        #[ink::test]
+        fn get_named_batch_works() {
+            let mut contract = Incrementer::new(0);
+            let apples = Hash::from([0x1; 32]);
+            let pears = Hash::from([0x2; 32]);
+            let plums = Hash::from([0x3; 32]);
+            assert_eq!(contract.inc_named(apples, 3), Ok(()));
+            assert_eq!(contract.inc_named(pears, 8), Ok(()));
+            assert_eq!(contract.get_named_batch(vec![pears, plums, apples]), vec![8, 0, 3]);
+        }
+
+        #[ink::test]
+        fn has_participated_works() {
+            let mut contract = Incrementer::with_config(0, Config {
+                min_increment: 0,
+                ..Config::default()
+            });
+            set_caller(AccountId::from([0x1; 32]));
+            assert!(!contract.has_participated());
+            assert_eq!(contract.inc_mine(0), Ok(()));
+            assert_eq!(contract.get_mine(), 0);
+            assert!(contract.has_participated());
+            set_caller(AccountId::from([0x2; 32]));
+            assert!(!contract.has_participated());
+        }
+
+        #[ink::test]
+        fn new_checked_works() {
+            let contradictory = Config {
+                min_increment: 10,
+                daily_quota: 5,
+                ..Config::default()
+            };
+            assert_eq!(
+                Incrementer::new_checked(contradictory).err(),
+                Some(Error::InvalidConfig)
+            );
+            let config = Config {
+                min_increment: 5,
+                daily_quota: 10,
+                ..Config::default()
+            };
+            let contract = Incrementer::new_checked(config.clone()).unwrap();
+            assert_eq!(contract.config(), config);
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "contradictory config")]
+        fn with_config_rejects_contradictory_config() {
+            Incrementer::with_config(0, Config {
+                min_increment: 10,
+                daily_quota: 5,
+                ..Config::default()
+            });
+        }
+
+        #[ink::test]
+        fn auto_pause_works() {
+            let mut contract = Incrementer::with_config(0, Config {
+                auto_pause_at: Some(100),
+                ..Config::default()
+            });
+            assert_eq!(contract.inc(120), Ok((0, 120)));
+            assert!(contract.snapshot().paused);
+            assert_eq!(contract.inc(1), Err(Error::Paused));
+            assert_eq!(contract.dec(1), Err(Error::Paused));
+            assert_eq!(contract.multicall(vec![Op::Inc(1)]), Err(Error::Paused));
+
+            let events: Vec<_> = ink_env::test::recorded_events().collect();
+            assert_eq!(events.len(), 1);
+            match <Event as scale::Decode>::decode(&mut &events[0].data[..]) {
+                Ok(Event::AutoPaused(AutoPaused { value })) => assert_eq!(value, 120),
+                _ => panic!("expected an AutoPaused event"),
+            }
+
+            set_caller(AccountId::from([0x0; 32]));
+            assert_eq!(contract.set_paused(false), Err(Error::NotOwner));
+            set_caller(AccountId::from([0x1; 32]));
+            assert_eq!(contract.set_paused(false), Ok(()));
+            assert_eq!(contract.inc(1), Ok((120, 121)));
+            assert!(!contract.snapshot().paused);
+        }
+
+        #[ink::test]
+        fn blocks_since_last_inc_works() {
+            let mut contract = Incrementer::new(0);
+            assert_eq!(contract.blocks_since_last_inc(), BlockNumber::MAX);
+            assert_eq!(contract.inc(1), Ok((0, 1)));
+            assert_eq!(contract.blocks_since_last_inc(), 0);
+            for _ in 0..3 {
+                ink_env::test::advance_block::<ink_env::DefaultEnvironment>().unwrap();
+            }
+            assert_eq!(contract.blocks_since_last_inc(), 3);
+            assert_eq!(contract.multicall(vec![Op::Inc(1)]), Ok(()));
+            assert_eq!(contract.blocks_since_last_inc(), 0);
+        }
+
+        #[ink::test]
+        fn cas_mine_works() {
+            let mut contract = Incrementer::new(0);
+            assert_eq!(contract.cas_mine(0, 5), Ok(5));
+            assert_eq!(contract.cas_mine(0, 5), Err(Error::StaleRead));
+            assert_eq!(contract.get_mine(), 5);
+            assert_eq!(contract.cas_mine(5, 2), Ok(7));
+        }
+
+        #[ink::test]
+        fn increment_policy_works() {
+            let mut contract = Incrementer::new(0);
+            assert_eq!(contract.policy(), IncrementPolicy::Linear);
+            assert_eq!(contract.inc(5), Ok((0, 5)));
+
+            assert_eq!(contract.set_policy(IncrementPolicy::Doubling), Ok(()));
+            assert_eq!(contract.inc(5), Ok((5, 15)));
+            assert_eq!(contract.multicall(vec![Op::Inc(1)]), Ok(()));
+            assert_eq!(contract.get(), 17);
+
+            assert_eq!(contract.set_policy(IncrementPolicy::Capped(3)), Ok(()));
+            assert_eq!(contract.inc(5), Ok((17, 20)));
+            assert_eq!(contract.inc(2), Ok((20, 22)));
+
+            set_caller(AccountId::from([0x0; 32]));
+            assert_eq!(contract.set_policy(IncrementPolicy::Linear), Err(Error::NotOwner));
+        }
+
+        #[ink::test]
+        fn multicall_emits_one_batch_event() {
+            let mut contract = Incrementer::new(10);
+            assert_eq!(
+                contract.multicall(vec![Op::Inc(5), Op::Dec(3), Op::Inc(2)]),
+                Ok(())
+            );
+
+            let events: Vec<_> = ink_env::test::recorded_events().collect();
+            assert_eq!(events.len(), 1);
+            match <Event as scale::Decode>::decode(&mut &events[0].data[..]) {
+                Ok(Event::BatchApplied(BatchApplied { ops, final_value })) => {
+                    assert_eq!(ops, 3);
+                    assert_eq!(final_value, 14);
+                }
+                _ => panic!("expected a BatchApplied event"),
+            }
+        }
+
+        #[ink::test]
+        fn high_water_works() {
+            let mut contract = Incrementer::new(0);
+            assert_eq!(contract.inc(50), Ok((0, 50)));
+            assert_eq!(contract.dec(20), Ok((50, 30)));
+            assert_eq!(contract.get(), 30);
+            assert_eq!(contract.high_water(), 50);
+
+            assert_eq!(contract.multicall(vec![Op::Inc(25)]), Ok(()));
+            assert_eq!(contract.high_water(), 55);
+        }
+
+        #[ink::test]
+        fn inc_by_oracle_works() {
+            let secret = ed25519_dalek::SecretKey::from_bytes(&[7; 32]).unwrap();
+            let public = ed25519_dalek::PublicKey::from(&secret);
+            let oracle = ed25519_dalek::Keypair { secret, public };
+            let oracle_key = AccountId::from(public.to_bytes());
+            let sign = |report: (i32, u64)| oracle.sign(&scale::Encode::encode(&report)).to_bytes();
+
+            let mut contract = Incrementer::with_oracle(10, oracle_key);
+            assert_eq!(contract.inc_by_oracle(5, sign((5, 0)), oracle_key), Ok(()));
+            assert_eq!(contract.get(), 15);
+            assert_eq!(contract.oracle_nonce(), 1);
+
+            // replayed, tampered with, or signed by someone else
+            assert_eq!(contract.inc_by_oracle(5, sign((5, 0)), oracle_key), Err(Error::BadOracleSig));
+            assert_eq!(contract.inc_by_oracle(50, sign((5, 1)), oracle_key), Err(Error::BadOracleSig));
+            let stranger = AccountId::from([1; 32]);
+            assert_eq!(contract.inc_by_oracle(5, sign((5, 1)), stranger), Err(Error::BadOracleSig));
+            assert_eq!(contract.get(), 15);
+
+            assert_eq!(contract.inc_by_oracle(-3, sign((-3, 1)), oracle_key), Ok(()));
+            assert_eq!(contract.get(), 12);
+            // reports count as increments like `inc` does
+            assert_eq!(contract.inc_count(), 2);
+            assert_eq!(contract.blocks_since_last_inc(), 0);
+            let caller = AccountId::from([0x1; 32]);
+            assert_eq!(contract.recent_events(), vec![(15, caller), (12, caller)]);
+        }
+
+        #[ink::test]
+        fn simulate_works() {
+            let mut contract = Incrementer::new(5);
+            assert_eq!(contract.simulate(vec![Op::Inc(10), Op::Dec(3)]), Ok(contract.get() + 7));
+            assert_eq!(contract.get(), 5);
+            assert_eq!(contract.inc_count(), 0);
+
+            assert_eq!(
+                contract.simulate(vec![Op::Inc(10), Op::Inc(i32::MAX)]),
+                Err(Error::Overflow)
+            );
+            assert_eq!(contract.get(), 5);
+            assert_eq!(contract.multicall(vec![Op::Inc(10), Op::Dec(3)]), Ok(()));
+            assert_eq!(contract.get(), 12);
+        }
+
+        #[ink::test]
+        fn zero_account_target_rejected() {
+            let zero = AccountId::from([0x0; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            let mut contract = Incrementer::new(0);
+            assert_eq!(contract.inc_mine(5), Ok(()));
+            assert_eq!(contract.transfer_mine(zero, 3), Err(Error::ZeroAccount));
+            assert_eq!(contract.get_mine(), 5);
+            assert_eq!(contract.get_for(zero), 0);
+            assert_eq!(contract.swap_mine(zero, bob), Err(Error::ZeroAccount));
+
+            assert_eq!(contract.transfer_mine(bob, 3), Ok(()));
+            assert_eq!(contract.get_mine(), 2);
+            assert_eq!(contract.get_for(bob), 3);
+        }
+
+        #[ink::test]
+        fn op_count_works() {
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            let charlie = AccountId::from([0x3; 32]);
+            let mut contract = Incrementer::new(0);
+            set_caller(bob);
+            assert_eq!(contract.inc_mine(5), Ok(()));
+            assert_eq!(contract.dec_mine(1), Ok(()));
+            assert_eq!(contract.transfer_mine(alice, 2), Ok(()));
+            // failed operations aren't counted
+            assert_eq!(contract.transfer_mine(alice, 10), Err(Error::InsufficientBalance));
+            assert_eq!(contract.my_op_count(), 3);
+            assert_eq!(contract.reset_op_counts(), Err(Error::NotOwner));
+
+            // a batch counts each of its personal operations
+            set_caller(charlie);
+            let ops = vec![Op::IncMine(1), Op::Inc(1), Op::IncMine(2), Op::IncMine(3)];
+            assert_eq!(contract.multicall(ops), Ok(()));
+            assert_eq!(contract.my_op_count(), 3);
+            assert_eq!(contract.get_mine(), 6);
+
+            set_caller(alice);
+            assert_eq!(contract.my_op_count(), 0);
+            assert_eq!(contract.reset_op_counts(), Ok(()));
+            set_caller(bob);
+            assert_eq!(contract.my_op_count(), 0);
+            assert_eq!(contract.get_mine(), 2);
+            set_caller(charlie);
+            assert_eq!(contract.my_op_count(), 0);
+        }
+
+        #[ink::test]
+        fn contribute_all_mine_respects_pause() {
+            let mut contract = Incrementer::with_config(0, Config {
+                auto_pause_at: Some(100),
+                ..Config::default()
+            });
+            assert_eq!(contract.inc_mine(60), Ok(()));
+            assert_eq!(contract.set_paused(true), Ok(()));
+            assert_eq!(contract.contribute_all_mine(), Err(Error::Paused));
+            assert_eq!(contract.get(), 0);
+            assert_eq!(contract.get_mine(), 60);
+
+            assert_eq!(contract.set_paused(false), Ok(()));
+            assert_eq!(contract.inc(50), Ok((0, 50)));
+            // crossing `auto_pause_at` this way pauses the contract like `inc` does
+            assert_eq!(contract.contribute_all_mine(), Ok(110));
+            assert!(contract.snapshot().paused);
+            assert_eq!(contract.inc(1), Err(Error::Paused));
+        }
     }
 }
@@ -160,9 +160,15 @@ fn testnet_genesis(
 					utxo::TransactionOutput {
 						value: 100 as utxo::Value,
 						pubkey: H256::from_slice(x.as_slice()),
+						lock: utxo::Lock::Pubkey,
+						lock_until: None,
+						relative_lock: 0,
 					}
 				)
-				.collect()
+				.collect(),
+			treasury_key: H256::zero(),
+			allow_empty_genesis: false,
+			reward_sink: None,
 		}),
 	}
 }
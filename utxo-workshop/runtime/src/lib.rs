@@ -227,8 +227,37 @@ impl sudo::Trait for Runtime {
 	type Call = Call;
 }
 
+parameter_types! {
+	pub const VerifySignatures: bool = true;
+	pub const TreasuryShare: Perbill = Perbill::from_percent(0);
+	pub const StrictOutputOrdering: bool = false;
+	pub const MaxFeeRatio: utxo::Value = 10;
+	pub const MinimumFee: utxo::Value = 0;
+	pub const MaturityPeriod: u64 = 100;
+	pub const DustThreshold: utxo::Value = 10;
+	pub const MinRelayFeePerByte: utxo::Value = 0;
+	pub const MaxDataLength: u32 = 80;
+	pub const CongestionTarget: u32 = 100;
+	pub const MaxInputs: u32 = 256;
+	pub const MaxOutputs: u32 = 256;
+	pub const MaxMultiSigKeys: u32 = 16;
+}
+
 impl utxo::Trait for Runtime {
 	type Event = Event;
+	type VerifySignatures = VerifySignatures;
+	type TreasuryShare = TreasuryShare;
+	type StrictOutputOrdering = StrictOutputOrdering;
+	type MaxFeeRatio = MaxFeeRatio;
+	type MinimumFee = MinimumFee;
+	type MaturityPeriod = MaturityPeriod;
+	type DustThreshold = DustThreshold;
+	type MinRelayFeePerByte = MinRelayFeePerByte;
+	type MaxDataLength = MaxDataLength;
+	type CongestionTarget = CongestionTarget;
+	type MaxInputs = MaxInputs;
+	type MaxOutputs = MaxOutputs;
+	type MaxMultiSigKeys = MaxMultiSigKeys;
 }
 
 construct_runtime!(
@@ -357,22 +386,43 @@ impl_runtime_apis! {
 
 	impl sp_transaction_pool::runtime_api::TaggedTransactionQueue<Block> for Runtime {
 		fn validate_transaction(tx: <Block as BlockT>::Extrinsic) -> TransactionValidity {
-			if let Some(&utxo::Call::spend(ref transaction)) = IsSubType::<Utxo, Runtime>::is_sub_type(&tx.function) {
-				// 1. Err case
-				match Utxo::validate_transaction(&transaction) {
-					Err(e) => {
-						sp_runtime::print(e);
-						return Err(TransactionValidityError::Invalid(InvalidTransaction::Custom(1)));
-					}
-					// 2. Race condition
-	
-					// 3. Valid transaction
-
-					Ok(vt) => {	return Ok(vt);	}
-				}
-			}
-			
-			Executive::validate_transaction(tx)
+			// every call that spends UTXOs is validated like the transaction it applies
+			let validity = match IsSubType::<Utxo, Runtime>::is_sub_type(&tx.function) {
+				Some(utxo::Call::spend(transaction)) => Utxo::validate_transaction(transaction),
+				Some(utxo::Call::batch_spend(transactions)) => Utxo::validate_batch(transactions),
+				Some(utxo::Call::spend_with_auto_change(inputs, recipient, amount, change_to, fee, valid_until)) =>
+					Utxo::with_change(inputs.clone(), *recipient, *amount, *change_to, *fee, *valid_until)
+						.and_then(|transaction| Utxo::validate_transaction(&transaction)),
+				Some(utxo::Call::consolidate(inputs, dest, fee)) =>
+					Utxo::consolidating(inputs.clone(), *dest, *fee)
+						.and_then(|transaction| Utxo::validate_transaction(&transaction)),
+				Some(utxo::Call::burn(inputs)) => Utxo::validate_burn(inputs),
+				_ => return Executive::validate_transaction(tx),
+			};
+			validity.map_err(|e| {
+				sp_runtime::print(e.as_str());
+				TransactionValidityError::Invalid(InvalidTransaction::Custom(e as u8))
+			})
+		}
+	}
+
+	#[cfg(feature = "runtime-benchmarks")]
+	impl frame_benchmarking::Benchmark<Block> for Runtime {
+		fn dispatch_benchmark(
+			module: Vec<u8>,
+			extrinsic: Vec<u8>,
+			lowest_range_values: Vec<u32>,
+			highest_range_values: Vec<u32>,
+			steps: Vec<u32>,
+			repeat: u32,
+		) -> Result<Vec<frame_benchmarking::BenchmarkResults>, sp_runtime::RuntimeString> {
+			use frame_benchmarking::Benchmarking;
+
+			let result = match module.as_slice() {
+				b"utxo" => Utxo::run_benchmark(extrinsic, lowest_range_values, highest_range_values, steps, repeat),
+				_ => Err("Benchmark not found for this pallet."),
+			};
+			result.map_err(|e| e.into())
 		}
 	}
 }
@@ -2,19 +2,68 @@ use super::Aura;
 use codec::{Decode, Encode};
 use frame_support::{
 	decl_event, decl_module, decl_storage,
-	dispatch::{DispatchResult, Vec},
+	dispatch::{DispatchError, DispatchResult, Vec},
 	ensure,
+	storage::IterableStorageMap,
+	traits::Get,
+	weights::{ClassifyDispatch, DispatchClass, PaysFee, SimpleDispatchInfo, WeighData, Weight},
 };
 use sp_core::{H256, H512};
 #[cfg(feature = "std")]
 use serde::{Deserialize, Serialize};
+use sp_core::ed25519;
 use sp_core::sr25519::{Public, Signature};
-use sp_runtime::traits::{BlakeTwo256, Hash, SaturatedConversion};
+use sp_runtime::traits::{BlakeTwo256, Hash, SaturatedConversion, Zero};
+use sp_runtime::{PerThing, Perbill};
 use sp_std::collections::btree_map::BTreeMap;
+use system::ensure_root;
 use sp_runtime::transaction_validity::{TransactionLongevity, ValidTransaction};
 
 pub trait Trait: system::Trait {
 	type Event: From<Event> + Into<<Self as system::Trait>::Event>;
+
+	/// Whether input signatures are checked. Only test configs should set this to false.
+	type VerifySignatures: Get<bool>;
+
+	/// Portion of each transaction's reward paid out to `TreasuryKey` instead of the validators.
+	type TreasuryShare: Get<Perbill>;
+
+	/// Whether outputs must be sorted by `(pubkey, value)`, so reordering them can't change the txid.
+	type StrictOutputOrdering: Get<bool>;
+
+	/// Largest allowed reward as a multiple of the total output value, or of the required fee
+	/// if that is larger (as for data-only transactions), guarding against fee mistakes.
+	type MaxFeeRatio: Get<Value>;
+
+	/// Smallest fee (`total_input - total_output`) a transaction must pay.
+	type MinimumFee: Get<Value>;
+
+	/// Blocks a validator reward UTXO must wait, after the block that created it, before it can be spent.
+	type MaturityPeriod: Get<u64>;
+
+	/// Smallest value an output may carry, so transactions can't fill `UtxoStore` with dust.
+	type DustThreshold: Get<Value>;
+
+	/// Fee every encoded byte of a transaction must pay, on top of the flat `MinimumFee`.
+	type MinRelayFeePerByte: Get<Value>;
+
+	/// Longest payload a `Lock::Data` output may carry.
+	type MaxDataLength: Get<u32>;
+
+	/// Transactions per block the chain handles at the base `MinimumFee`; each further multiple
+	/// of it, averaged over `CONGESTION_WINDOW` blocks, raises the minimum by another `MinimumFee`.
+	/// Zero turns the scaling off.
+	type CongestionTarget: Get<u32>;
+
+	/// Most inputs a transaction may spend, bounding the work its validation does.
+	type MaxInputs: Get<u32>;
+
+	/// Most outputs a transaction may create.
+	type MaxOutputs: Get<u32>;
+
+	/// Most keys a `Lock::MultiSig` output may list, and witness signatures an input may carry,
+	/// bounding the signature checks spending one input costs.
+	type MaxMultiSigKeys: Get<u32>;
 }
 
 #[cfg_attr(feature="std", derive(Serialize, Deserialize))]
@@ -22,38 +71,392 @@ pub trait Trait: system::Trait {
 pub struct TransactionInput {
 	pub outpoint: H256,
 	pub sigscript: H512,
+	/// Further signatures for inputs spending a `Lock::MultiSig` output, or the preimage
+	/// claiming a `Lock::Htlc` one.
+	pub witness: Vec<H512>,
 }
 
 pub type Value = u128;
 
+/// Sub-units per `Value` unit that treasury cuts are computed in, matching `Perbill`'s accuracy.
+pub const REWARD_PRECISION: Value = 1_000_000_000;
+
+/// Finished blocks whose transaction counts `congestion_multiplier` averages.
+pub const CONGESTION_WINDOW: usize = 10;
+
+/// Extra spending conditions on top of the output's `pubkey`.
+#[cfg_attr(feature="std", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Encode, Decode, Hash, Debug)]
+pub enum Lock {
+	/// Only `pubkey` can spend the output.
+	Pubkey,
+	/// `pubkey` can spend the output anytime, `fallback` only once `deadline` has passed.
+	Escrow { fallback: H256, deadline: u64 },
+	/// At least `threshold` of `keys` must sign, in the input's `sigscript` and `witness`. The
+	/// output's `pubkey` only indexes it and can't spend it alone.
+	MultiSig { threshold: u8, keys: Vec<H256> },
+	/// Hash time-locked, for atomic swaps: `claim_key` can spend the output up to block `timeout`
+	/// by revealing, as the input's first `witness` entry, a preimage hashing to `hashlock`.
+	/// After `timeout`, `refund_key` can spend it. The output's `pubkey` can't spend it alone.
+	Htlc { hashlock: H256, timeout: u64, claim_key: H256, refund_key: H256 },
+	/// Anchors `data` in the transaction, like Bitcoin's `OP_RETURN`. The output must be worth
+	/// nothing and is never stored, so it can't be spent.
+	Data { data: Vec<u8> },
+}
+
+impl Lock {
+	/// Whether the output only carries data, and so is never stored.
+	pub fn is_data(&self) -> bool {
+		match self {
+			Lock::Data { .. } => true,
+			_ => false,
+		}
+	}
+
+	/// Whether an output can carry this lock: a multisig needs a threshold its keys can meet, and
+	/// at most `max_multisig_keys` keys. `Lock::Data` is checked against `MaxDataLength` separately.
+	pub fn is_well_formed(&self, max_multisig_keys: u32) -> bool {
+		match self {
+			Lock::MultiSig { threshold, keys } => {
+				*threshold > 0 && *threshold as usize <= keys.len() && keys.len() <= max_multisig_keys as usize
+			}
+			_ => true,
+		}
+	}
+}
+
+impl Default for Lock {
+	fn default() -> Self {
+		Lock::Pubkey
+	}
+}
+
 #[cfg_attr(feature="std", derive(Serialize, Deserialize))]
 #[derive(PartialEq, Eq, PartialOrd, Ord, Default, Clone, Encode, Decode, Hash, Debug)]
 pub struct TransactionOutput {
 	pub value: Value,
 	pub pubkey: H256,
+	pub lock: Lock,
+	/// First block the output can be spent in, e.g. for vesting.
+	pub lock_until: Option<u64>,
+	/// Blocks that must pass after the one creating the output before it can be spent.
+	pub relative_lock: u32,
+}
+
+/// `TransactionOutput` as stored before `lock_until` was added, for `on_runtime_upgrade`. Two
+/// layouts read as version 0: the original `{ value, pubkey }`, and `{ value, pubkey, lock }`
+/// once `lock` was added. An output that ends after its pubkey has a plain `Lock::Pubkey`.
+struct TransactionOutputV0 {
+	value: Value,
+	pubkey: H256,
+	lock: Lock,
+}
+
+impl Decode for TransactionOutputV0 {
+	fn decode<I: codec::Input>(input: &mut I) -> Result<Self, codec::Error> {
+		let value = Value::decode(input)?;
+		let pubkey = H256::decode(input)?;
+		let lock = match input.remaining_len()? {
+			Some(0) => Lock::Pubkey,
+			_ => Lock::decode(input)?,
+		};
+		Ok(TransactionOutputV0 { value, pubkey, lock })
+	}
+}
+
+/// `TransactionOutput` as stored before `relative_lock` was added, for `on_runtime_upgrade`.
+#[derive(Decode)]
+struct TransactionOutputV1 {
+	value: Value,
+	pubkey: H256,
+	lock: Lock,
+	lock_until: Option<u64>,
 }
 
+/// Version of the `UtxoStore` layout and what is derived from it: 1 added `lock_until`,
+/// 2 `OwnedOutpoints`, 3 `TotalSupply`, 4 `relative_lock`.
+pub const STORAGE_VERSION: u32 = 4;
+
 #[cfg_attr(feature="std", derive(Serialize, Deserialize))]
 #[derive(PartialEq, Eq, PartialOrd, Ord, Default, Clone, Encode, Decode, Hash, Debug)]
 pub struct Transaction {
 	pub inputs: Vec<TransactionInput>,
 	pub outputs: Vec<TransactionOutput>,
+	/// Last block the transaction can be included in. It is signed, so a stale broadcast can't
+	/// be revived by changing it.
+	pub valid_until: u64,
+	/// Authority paid this transaction's reward directly, in a UTXO of its own, rather than
+	/// through the shared pool. Ignored, falling back to the pool, if it isn't a current authority.
+	pub tip_to: Option<H256>,
+}
+
+/// Weight of `spend`: a flat base plus a signature check and UTXO removal per input and an insert
+/// per output, so large transactions pay for the work they cause. Coefficients come from the
+/// `spend` benchmark, which the runtime serves through `dispatch_benchmark` for the `utxo` module.
+pub struct SpendWeight;
+
+impl SpendWeight {
+	pub const BASE: Weight = 10_000;
+	pub const PER_INPUT: Weight = 25_000;
+	pub const PER_OUTPUT: Weight = 5_000;
+
+	pub fn weight_of(inputs: usize, outputs: usize) -> Weight {
+		Self::BASE
+			.saturating_add(Self::PER_INPUT.saturating_mul(inputs.saturated_into()))
+			.saturating_add(Self::PER_OUTPUT.saturating_mul(outputs.saturated_into()))
+	}
+}
+
+impl WeighData<(&Transaction,)> for SpendWeight {
+	fn weigh_data(&self, (transaction,): (&Transaction,)) -> Weight {
+		Self::weight_of(transaction.inputs.len(), transaction.outputs.len())
+	}
+}
+
+impl ClassifyDispatch<(&Transaction,)> for SpendWeight {
+	fn classify_dispatch(&self, _: (&Transaction,)) -> DispatchClass {
+		DispatchClass::Normal
+	}
+}
+
+impl PaysFee<(&Transaction,)> for SpendWeight {}
+
+impl WeighData<(&Vec<Transaction>,)> for SpendWeight {
+	fn weigh_data(&self, (transactions,): (&Vec<Transaction>,)) -> Weight {
+		transactions.iter().fold(0, |total: Weight, transaction| total.saturating_add(self.weigh_data((transaction,))))
+	}
+}
+
+impl ClassifyDispatch<(&Vec<Transaction>,)> for SpendWeight {
+	fn classify_dispatch(&self, _: (&Vec<Transaction>,)) -> DispatchClass {
+		DispatchClass::Normal
+	}
+}
+
+impl PaysFee<(&Vec<Transaction>,)> for SpendWeight {}
+
+impl WeighData<(&Vec<TransactionInput>, &H256, &Value)> for SpendWeight {
+	fn weigh_data(&self, (inputs, _, _): (&Vec<TransactionInput>, &H256, &Value)) -> Weight {
+		Self::weight_of(inputs.len(), 1)
+	}
+}
+
+impl ClassifyDispatch<(&Vec<TransactionInput>, &H256, &Value)> for SpendWeight {
+	fn classify_dispatch(&self, _: (&Vec<TransactionInput>, &H256, &Value)) -> DispatchClass {
+		DispatchClass::Normal
+	}
+}
+
+impl PaysFee<(&Vec<TransactionInput>, &H256, &Value)> for SpendWeight {}
+
+impl WeighData<(&Vec<TransactionInput>, &H256, &Value, &H256, &Value, &u64)> for SpendWeight {
+	fn weigh_data(&self, (inputs, ..): (&Vec<TransactionInput>, &H256, &Value, &H256, &Value, &u64)) -> Weight {
+		// the payment and, at most, one change output
+		Self::weight_of(inputs.len(), 2)
+	}
+}
+
+impl ClassifyDispatch<(&Vec<TransactionInput>, &H256, &Value, &H256, &Value, &u64)> for SpendWeight {
+	fn classify_dispatch(&self, _: (&Vec<TransactionInput>, &H256, &Value, &H256, &Value, &u64)) -> DispatchClass {
+		DispatchClass::Normal
+	}
+}
+
+impl PaysFee<(&Vec<TransactionInput>, &H256, &Value, &H256, &Value, &u64)> for SpendWeight {}
+
+impl WeighData<(&Vec<TransactionInput>,)> for SpendWeight {
+	fn weigh_data(&self, (inputs,): (&Vec<TransactionInput>,)) -> Weight {
+		Self::weight_of(inputs.len(), 0)
+	}
+}
+
+impl ClassifyDispatch<(&Vec<TransactionInput>,)> for SpendWeight {
+	fn classify_dispatch(&self, _: (&Vec<TransactionInput>,)) -> DispatchClass {
+		DispatchClass::Normal
+	}
+}
+
+impl PaysFee<(&Vec<TransactionInput>,)> for SpendWeight {}
+
+/// Why a transaction was rejected. The pool reports it as `InvalidTransaction::Custom(error as u8)`.
+#[derive(PartialEq, Eq, Clone, Copy, Encode, Decode, Debug)]
+pub enum UtxoError {
+	NoInputs,
+	NoOutputs,
+	DuplicateInput,
+	DuplicateOutput,
+	OutputValueZero,
+	OutputsNotCanonical,
+	TransactionExpired,
+	/// An input's UTXO is neither in the store nor allowed to be pending here.
+	MissingInput,
+	SpenderFrozen,
+	RecipientFrozen,
+	BadSignature,
+	EscrowDeadlineNotReached,
+	/// Reported by `can_spend` when the key is neither the owner nor a due escrow fallback.
+	NotOwner,
+	OutputAlreadyExists,
+	OutputExceedsInput,
+	InputValueOverflow,
+	OutputValueOverflow,
+	OutputIndexOverflow,
+	RewardOverflow,
+	/// The reward is more than `MaxFeeRatio` times the total output value or the required fee.
+	ExcessiveFee,
+	/// The fee is below `MinimumFee` times the `congestion_multiplier`.
+	FeeTooLow,
+	/// An input's UTXO has a `lock_until` past the current block.
+	OutputLocked,
+	/// An input is a validator reward younger than `MaturityPeriod`.
+	RewardImmature,
+	/// An output is worth less than `DustThreshold`.
+	OutputBelowDustThreshold,
+	/// The fee is below `MinRelayFeePerByte` times the encoded transaction length.
+	FeeBelowRelayMinimum,
+	/// `TotalSupply` differs from the value held in UTXOs and the reward pool.
+	SupplyMismatch,
+	/// A `Lock::MultiSig` output has a zero threshold, fewer keys than its threshold or more
+	/// than `MaxMultiSigKeys`.
+	InvalidMultiSig,
+	/// Fewer distinct keys of a `Lock::MultiSig` input signed than its threshold.
+	MultiSigThresholdNotMet,
+	/// A `Lock::Htlc` claim's preimage is missing or doesn't hash to the `hashlock`.
+	HtlcBadPreimage,
+	/// A `Lock::Htlc` refund before its `timeout` has passed.
+	HtlcTimeoutNotReached,
+	/// A `Lock::Htlc` claim after its `timeout`.
+	HtlcExpired,
+	/// An input's UTXO was created less than its `relative_lock` blocks ago.
+	RelativeLockNotReached,
+	/// A `Lock::Data` output is worth something, or carries more than `MaxDataLength` bytes.
+	InvalidDataOutput,
+	/// The transaction spends more than `MaxInputs` inputs.
+	TooManyInputs,
+	/// The transaction creates more than `MaxOutputs` outputs.
+	TooManyOutputs,
+	/// An input carries more witness signatures than `MaxMultiSigKeys`, or than the keys of the
+	/// `Lock::MultiSig` output it spends.
+	TooManySignatures,
+}
+
+impl UtxoError {
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			UtxoError::NoInputs => "No inputs",
+			UtxoError::NoOutputs => "No outputs",
+			UtxoError::DuplicateInput => "each input must only be used once",
+			UtxoError::DuplicateOutput => "each output must only be used once",
+			UtxoError::OutputValueZero => "Output value must be nonzero",
+			UtxoError::OutputsNotCanonical => "outputs not canonical",
+			UtxoError::TransactionExpired => "transaction expired",
+			UtxoError::MissingInput => "utxo does not exist",
+			UtxoError::SpenderFrozen => "spender frozen",
+			UtxoError::RecipientFrozen => "recipient frozen",
+			UtxoError::BadSignature => "signature must be valid",
+			UtxoError::EscrowDeadlineNotReached => "escrow deadline not reached",
+			UtxoError::NotOwner => "utxo is not owned by this key",
+			UtxoError::OutputAlreadyExists => "output already exists",
+			UtxoError::OutputExceedsInput => "output value must not exceed input value",
+			UtxoError::InputValueOverflow => "input value overflow",
+			UtxoError::OutputValueOverflow => "output value overflow",
+			UtxoError::OutputIndexOverflow => "output index overflow",
+			UtxoError::RewardOverflow => "reward overflow",
+			UtxoError::ExcessiveFee => "fee exceeds the allowed multiple of the output value",
+			UtxoError::FeeTooLow => "fee below the minimum",
+			UtxoError::OutputLocked => "utxo is time-locked",
+			UtxoError::RewardImmature => "reward utxo is not mature yet",
+			UtxoError::OutputBelowDustThreshold => "output value below the dust threshold",
+			UtxoError::FeeBelowRelayMinimum => "fee below the relay minimum for its size",
+			UtxoError::SupplyMismatch => "total supply does not match the utxo set",
+			UtxoError::InvalidMultiSig => "multisig output threshold is zero or exceeds its keys, or it has too many keys",
+			UtxoError::MultiSigThresholdNotMet => "too few multisig keys signed the input",
+			UtxoError::HtlcBadPreimage => "htlc preimage does not match the hashlock",
+			UtxoError::HtlcTimeoutNotReached => "htlc cannot be refunded before its timeout",
+			UtxoError::HtlcExpired => "htlc cannot be claimed after its timeout",
+			UtxoError::RelativeLockNotReached => "input is still relatively time-locked",
+			UtxoError::InvalidDataOutput => "data output must be worth nothing and within the length limit",
+			UtxoError::TooManyInputs => "too many inputs",
+			UtxoError::TooManyOutputs => "too many outputs",
+			UtxoError::TooManySignatures => "too many witness signatures",
+		}
+	}
+}
+
+impl From<UtxoError> for DispatchError {
+	fn from(error: UtxoError) -> Self {
+		DispatchError::Other(error.as_str())
+	}
 }
 
 decl_storage! {
 	trait Store for Module<T: Trait> as Utxo {
 		UtxoStore build(|config: &GenesisConfig| {
-			config.genesis_utxos
+			assert!(
+				config.allow_empty_genesis || !config.genesis_utxos.is_empty(),
+				"genesis_utxos is empty, so no coins could ever be spent; set allow_empty_genesis to build anyway"
+			);
+			let mut utxos = config.genesis_utxos
 				.iter()
 				.cloned()
 				.map(|u| (BlakeTwo256::hash_of(&u), u))
-				.collect::<Vec<_>>()
+				.collect::<Vec<_>>();
+			// insert in hash order, whatever order the chain spec lists them in
+			utxos.sort_by_key(|(hash, _)| *hash);
+			utxos
 		}): map hasher(identity) H256 => Option<TransactionOutput>;
 		pub RewardTotal get(reward_total): Value;
+		/// All value in circulation: every UTXO plus `RewardTotal`. Only `mint`, `burn` and
+		/// `spend_unchecked` change it.
+		pub TotalSupply get(total_supply) build(|config: &GenesisConfig| {
+			config.genesis_utxos.iter().fold(0, |total: Value, utxo| total.saturating_add(utxo.value))
+		}): Value;
+		/// Every fee ever paid. Unlike `RewardTotal` it is never drained.
+		pub TotalFeesCollected get(total_fees_collected): Value;
+		/// The treasury's cut below one whole unit, in `REWARD_PRECISION` sub-units, carried over to
+		/// the next reward instead of being rounded away.
+		pub RewardDust get(reward_dust): Value;
+		pub TreasuryKey get(treasury_key) config(): H256;
+		/// Keys that can neither spend nor receive UTXOs.
+		pub Frozen get(is_frozen): map hasher(identity) H256 => bool;
+		/// Outpoints of the UTXOs each pubkey owns, so wallets need not scan `UtxoStore`.
+		pub OwnedOutpoints get(owned_outpoints) build(|config: &GenesisConfig| {
+			let mut owned = BTreeMap::<H256, Vec<H256>>::new();
+			for utxo in &config.genesis_utxos {
+				owned.entry(utxo.pubkey).or_default().push(BlakeTwo256::hash_of(utxo));
+			}
+			owned.into_iter().collect::<Vec<_>>()
+		}): map hasher(identity) H256 => Vec<H256>;
+		/// Block each unspent UTXO with a `relative_lock` was created in; genesis ones read as 0.
+		pub CreatedAt get(created_at): map hasher(identity) H256 => u64;
+		/// Block each unspent validator reward UTXO was created in.
+		pub RewardMaturity get(reward_created_at): map hasher(identity) H256 => Option<u64>;
+		/// While set, typically during a chain's bootstrap, every block reward goes to this key
+		/// rather than being split among the authorities.
+		pub RewardSink get(reward_sink) build(|config: &GenesisConfig| config.reward_sink): Option<H256>;
+		/// Layout of `UtxoStore`: chains started before `lock_until` existed read as version 0.
+		pub StorageVersion get(storage_version) build(|_| STORAGE_VERSION): u32;
+		/// `authorities()` as of the Aura set hashing to `AuthoritiesHash`.
+		pub CachedAuthorities get(cached_authorities): Vec<H256>;
+		pub AuthoritiesHash get(authorities_hash): H256;
+		/// Mixed into each minted output's hash, so minting the same output twice gives two UTXOs.
+		pub MintNonce get(mint_nonce): u64;
+		/// Extra keys each owner lets spend its UTXOs, e.g. a custodian's.
+		pub SpendAuthority get(spend_authority): map hasher(identity) H256 => Vec<H256>;
+		/// Signed into each `set_spend_authority` message, so an old allowlist can't be replayed.
+		pub SpendAuthorityNonce get(spend_authority_nonce): map hasher(identity) H256 => u64;
+		/// Transactions applied so far in the current block.
+		pub BlockTransactions get(block_transactions): u32;
+		/// Transaction counts of the last `CONGESTION_WINDOW` finished blocks, oldest first.
+		pub RecentTransactions get(recent_transactions): Vec<u32>;
 	}
 
 	add_extra_genesis {
-		config(genesis_utxos): Vec<TransactionOutput>
+		config(genesis_utxos): Vec<TransactionOutput>;
+		/// Lets a chain start without any UTXO, e.g. one whose coins are all minted later.
+		config(allow_empty_genesis): bool;
+		/// Starts the chain with a `RewardSink`, e.g. for reproducible test chains.
+		config(reward_sink): Option<H256>;
 	}
 }
 
@@ -62,187 +465,1133 @@ decl_module! {
 	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
 		fn deposit_event() = default;
 
+		fn on_runtime_upgrade() {
+			Self::migrate_utxo_store();
+		}
+
+		#[weight = SpendWeight]
 		pub fn spend(_origin, transaction: Transaction) -> DispatchResult {
-			let valid_transaction = Self::validate_transaction(&transaction)?;
-			
-			Self::update_storage(&transaction, valid_transaction.priority as Value)?;
+			Self::apply_transaction(transaction)
+		}
+
+		/// Applies `transactions` in order, or none of them if any is invalid. A transaction may
+		/// spend the outputs of an earlier one in the batch.
+		#[weight = SpendWeight]
+		pub fn batch_spend(_origin, transactions: Vec<Transaction>) -> DispatchResult {
+			Self::apply_batch(transactions)
+		}
+
+		/// Pays `amount` to `recipient` and returns whatever the inputs hold beyond `amount` and
+		/// `fee` to `change_to`. The inputs sign the transaction `with_change` builds.
+		#[weight = SpendWeight]
+		pub fn spend_with_auto_change(
+			_origin,
+			inputs: Vec<TransactionInput>,
+			recipient: H256,
+			amount: Value,
+			change_to: H256,
+			fee: Value,
+			valid_until: u64,
+		) -> DispatchResult {
+			let transaction = Self::with_change(inputs, recipient, amount, change_to, fee, valid_until)?;
+			Self::apply_transaction(transaction)
+		}
+
+		/// Merges the UTXOs behind `inputs` into one output to `dest`, worth their total less `fee`.
+		/// The inputs sign the transaction `consolidating` builds.
+		#[weight = SpendWeight]
+		pub fn consolidate(_origin, inputs: Vec<TransactionInput>, dest: H256, fee: Value) -> DispatchResult {
+			let transaction = Self::consolidating(inputs, dest, fee)?;
+			Self::apply_transaction(transaction)
+		}
+
+		/// Privileged: lets root rebalance UTXOs without conserving value, so the outputs may be
+		/// worth more than the inputs. Inputs must still exist and be signed, and no fee is taken.
+		pub fn spend_unchecked(origin, transaction: Transaction) -> DispatchResult {
+			ensure_root(origin)?;
+			Self::structural_check(&transaction)?;
+			let inputs = Self::load_inputs(&transaction);
+			ensure!(inputs.iter().all(|(_, utxo)| utxo.is_some()), UtxoError::MissingInput);
+			Self::validate_loaded_transaction(&transaction, &inputs, false)?;
+
+			let total_input = inputs.iter()
+				.filter_map(|(_, utxo)| utxo.as_ref())
+				.fold(0, |total: Value, utxo| total.saturating_add(utxo.value));
+			let total_output = transaction.outputs.iter()
+				.fold(0, |total: Value, output| total.saturating_add(output.value));
+			let created = Self::update_storage(&transaction, &inputs, 0)?;
+			<TotalSupply>::mutate(|supply| {
+				*supply = supply.saturating_add(total_output).saturating_sub(total_input)
+			});
+			Self::deposit_event(Event::TransactionSuccess(transaction, 0, created));
+
+			Ok(())
+		}
+
+		/// Removes every UTXO worth less than `threshold` and moves their value into the reward pool.
+		/// Operational and heavy: it scans the whole `UtxoStore`, so rather than being metered per
+		/// UTXO it claims half of the runtime's `MaximumBlockWeight`.
+		#[weight = SimpleDispatchInfo::FixedOperational(500_000_000)]
+		pub fn compact_dust(origin, threshold: Value) -> DispatchResult {
+			ensure_root(origin)?;
+			let dust: Vec<(H256, TransactionOutput)> = <UtxoStore>::iter()
+				.filter(|(_, utxo)| utxo.value < threshold)
+				.collect();
+
+			let mut reclaimed: Value = 0;
+			for (_, utxo) in &dust {
+				reclaimed = reclaimed.checked_add(utxo.value).ok_or("reclaimed value overflow")?;
+			}
+			let new_total = <RewardTotal>::get().checked_add(reclaimed).ok_or("reward overflow")?;
+
+			for (hash, utxo) in &dust {
+				Self::remove_utxo(hash, utxo.pubkey);
+			}
+			<RewardTotal>::put(new_total);
+			Self::deposit_event(Event::DustCompacted(dust.len() as u32, reclaimed));
+
+			Ok(())
+		}
+
+		/// Creates `outputs` out of thin air, e.g. for a testnet faucet.
+		pub fn mint(origin, outputs: Vec<TransactionOutput>) -> DispatchResult {
+			ensure_root(origin)?;
+			ensure!(outputs.iter().all(|output| output.value > 0), UtxoError::OutputValueZero);
+			let max_multisig_keys = T::MaxMultiSigKeys::get();
+			ensure!(outputs.iter().all(|output| output.lock.is_well_formed(max_multisig_keys)), UtxoError::InvalidMultiSig);
+
+			let mut supply = <TotalSupply>::get();
+			for output in &outputs {
+				supply = supply.checked_add(output.value).ok_or("total supply overflow")?;
+			}
+
+			let mut nonce = <MintNonce>::get();
+			let mut minted = Vec::new();
+			for output in outputs {
+				let hash = BlakeTwo256::hash_of(&(&output, nonce));
+				nonce = nonce.checked_add(1).ok_or("mint nonce overflow")?;
+				Self::insert_utxo(hash, output);
+				minted.push(hash);
+			}
+			<MintNonce>::put(nonce);
+			<TotalSupply>::put(supply);
+			Self::deposit_event(Event::Minted(minted));
+
+			Ok(())
+		}
+
+		/// Destroys the UTXOs behind `inputs` for good: nothing is created and no reward is paid.
+		/// Each input signs `burning(inputs)`, a transaction without outputs.
+		#[weight = SpendWeight]
+		pub fn burn(_origin, inputs: Vec<TransactionInput>) -> DispatchResult {
+			let (owners, total) = Self::check_burn(&inputs)?;
+
+			let outpoints: Vec<H256> = inputs.iter().map(|input| input.outpoint).collect();
+			for (outpoint, owner) in outpoints.iter().zip(owners) {
+				Self::remove_utxo(outpoint, owner);
+			}
+			<TotalSupply>::mutate(|supply| *supply = supply.saturating_sub(total));
+			Self::deposit_event(Event::Burned(outpoints, total));
+
+			Ok(())
+		}
+
+		/// Freezes or unfreezes `pubkey`.
+		pub fn set_frozen(origin, pubkey: H256, frozen: bool) -> DispatchResult {
+			ensure_root(origin)?;
+			if frozen {
+				<Frozen>::insert(pubkey, true);
+			} else {
+				<Frozen>::remove(pubkey);
+			}
+			Ok(())
+		}
+
+		/// Sets or, to end the bootstrap phase, clears the `RewardSink`.
+		pub fn set_reward_sink(origin, sink: Option<H256>) -> DispatchResult {
+			ensure_root(origin)?;
+			<RewardSink>::set(sink);
+			Ok(())
+		}
+
+		/// Replaces the keys allowed to spend `owner`'s UTXOs; an empty list revokes them all.
+		/// `owner` signs `spend_authority_payload` with its current `SpendAuthorityNonce`.
+		pub fn set_spend_authority(_origin, owner: H256, authorized: Vec<H256>, signature: H512) -> DispatchResult {
+			let nonce = <SpendAuthorityNonce>::get(owner);
+			let payload = Self::spend_authority_payload(owner, &authorized, nonce);
+			ensure!(Self::signed_by(&signature, &payload, owner), UtxoError::BadSignature);
+
+			<SpendAuthorityNonce>::insert(owner, nonce.checked_add(1).ok_or("spend authority nonce overflow")?);
+			if authorized.is_empty() {
+				<SpendAuthority>::remove(owner);
+			} else {
+				<SpendAuthority>::insert(owner, &authorized);
+			}
+			Self::deposit_event(Event::SpendAuthoritySet(owner, authorized));
 
-			// 3. emit success event
-			Self::deposit_event(Event::TransactionSuccess(transaction));
-			
 			Ok(())
 		}
 
+		/// Fixed: one reward UTXO per authority, and the authority set is small.
+		#[weight = SimpleDispatchInfo::FixedNormal(50_000)]
 		fn on_finalize() {
-			let auth: Vec<_> = Aura::authorities().iter().map(|x| {
-				let r: &Public = x.as_ref();
-				r.0.into()
-			}).collect();
-			Self::disperse_rewards(&auth);
+			match <RewardSink>::get() {
+				Some(sink) => Self::disperse_rewards(&[sink]),
+				None => Self::disperse_rewards(&Self::authorities()),
+			}
+			Self::record_block_transactions();
 		}
 	}
 }
 
 decl_event! {
 	pub enum Event {
-		TransactionSuccess(Transaction),
+		/// A transaction was applied, paying the given fee (`total_input - total_output`) and
+		/// creating the UTXOs at these outpoints: its value outputs in order, then any treasury or tip UTXO.
+		TransactionSuccess(Transaction, Value, Vec<H256>),
+		/// `compact_dust` removed this many UTXOs, reclaiming their total value into the reward pool.
+		DustCompacted(u32, Value),
+		/// At this block, each of these validators received a reward UTXO of the given value.
+		RewardDispersed(u64, Value, Vec<H256>),
+		/// Root minted UTXOs at these outpoints.
+		Minted(Vec<H256>),
+		/// `burn` destroyed the UTXOs at these outpoints, worth this much in total.
+		Burned(Vec<H256>, Value),
+		/// The owner now lets these keys spend its UTXOs.
+		SpendAuthoritySet(H256, Vec<H256>),
 	}
 }
 
 impl<T: Trait> Module<T> {
 
+	/// The bytes every input signs: the chain's genesis block hash, so signatures can't be replayed
+	/// on another chain, followed by the transaction with all sigscripts zeroed and witnesses cleared. Validation computes
+	/// them once per transaction, and clients can reuse them to produce signatures.
 	pub fn get_simple_transaction(transaction: &Transaction) -> Vec<u8> {
+		#[cfg(test)]
+		tests::SIMPLE_TRANSACTION_CALLS.with(|calls| calls.set(calls.get() + 1));
+
 		let mut trx = transaction.clone();
 		for input in trx.inputs.iter_mut() {
 			input.sigscript = H512::zero();
+			input.witness.clear();
 		}
-		trx.encode()
+		(<system::Module<T>>::block_hash(T::BlockNumber::zero()), trx).encode()
 	}
 
-	fn update_storage(transaction: &Transaction, reward: Value) -> DispatchResult {
-		let new_total: Value = <RewardTotal>::get()
-			.checked_add(reward)
-			.ok_or("reward overflow")?;
-		<RewardTotal>::put(new_total);
-
-		// 1. Remove UTXO from utxoStrore
-		for input in &transaction.inputs {
-			<UtxoStore>::remove(input.outpoint);
+	/// Checks whether `pubkey` could spend `outpoint` at `current_block`, without building a
+	/// transaction.
+	pub fn can_spend(outpoint: H256, pubkey: H256, current_block: u64) -> Result<(), UtxoError> {
+		let utxo = <UtxoStore>::get(&outpoint).ok_or(UtxoError::MissingInput)?;
+		Self::ensure_unlocked(&outpoint, &utxo, current_block)?;
+		match &utxo.lock {
+			Lock::MultiSig { threshold, keys } => {
+				// other keys' signatures are needed unless the threshold is one
+				ensure!(*threshold == 1 && keys.contains(&pubkey), UtxoError::NotOwner);
+				return Ok(());
+			}
+			// the claim also needs the preimage, which only the spender can know about
+			Lock::Htlc { timeout, claim_key, refund_key, .. } => {
+				return match pubkey {
+					key if key == *claim_key && current_block <= *timeout => Ok(()),
+					key if key == *refund_key => {
+						ensure!(current_block > *timeout, UtxoError::HtlcTimeoutNotReached);
+						Ok(())
+					}
+					key if key == *claim_key => Err(UtxoError::HtlcExpired),
+					_ => Err(UtxoError::NotOwner),
+				};
+			}
+			_ => {}
 		}
-		// 2. Create new UTXOs in utxostore
-		let mut index: u64 = 0; 
-		for output in &transaction.outputs {
-			let hash = BlakeTwo256::hash_of(&(&transaction.encode(), index));
-			index = index.checked_add(1).ok_or("output index overflow")?;
-			<UtxoStore>::insert(hash, output);
+		if utxo.pubkey == pubkey || <SpendAuthority>::get(utxo.pubkey).contains(&pubkey) {
+			return Ok(());
+		}
+		match utxo.lock {
+			Lock::Escrow { fallback, deadline } if fallback == pubkey => {
+				ensure!(current_block > deadline, UtxoError::EscrowDeadlineNotReached);
+				Ok(())
+			}
+			_ => Err(UtxoError::NotOwner),
+		}
+	}
+
+	/// Checks that nothing but its spending condition holds `utxo` back at `current_block`: its
+	/// owner isn't frozen and its time-locks and any reward maturity have passed.
+	fn ensure_unlocked(outpoint: &H256, utxo: &TransactionOutput, current_block: u64) -> Result<(), UtxoError> {
+		ensure!(!<Frozen>::get(utxo.pubkey), UtxoError::SpenderFrozen);
+		ensure!(
+			utxo.lock_until.map_or(true, |height| height <= current_block),
+			UtxoError::OutputLocked
+		);
+		ensure!(
+			<CreatedAt>::get(outpoint).saturating_add(utxo.relative_lock as u64) <= current_block,
+			UtxoError::RelativeLockNotReached
+		);
+		if let Some(created) = <RewardMaturity>::get(outpoint) {
+			ensure!(
+				current_block >= created.saturating_add(T::MaturityPeriod::get()),
+				UtxoError::RewardImmature
+			);
 		}
 		Ok(())
 	}
 
-	fn disperse_rewards(authorities: &[H256]) {
-		// 1. divide rewards fairly
-		let reward = <RewardTotal>::take();
-		let share_value: Value = reward
-			.checked_div(authorities.len() as Value)
-			.ok_or("No authorities")
-			.unwrap();
-		
-		if share_value == 0 { return }
+	/// Lets wallets check a signature before broadcasting: whether input `input_index` of `transaction`
+	/// was signed by `pubkey`. Out of range indices are never signed.
+	pub fn verify_input_signature(transaction: &Transaction, input_index: usize, pubkey: H256) -> bool {
+		match transaction.inputs.get(input_index) {
+			Some(input) => Self::signed_by(&input.sigscript, &Self::get_simple_transaction(transaction), pubkey),
+			None => false,
+		}
+	}
 
-		let remainder = reward
-			.checked_sub(share_value * authorities.len() as Value)
-			.ok_or("Sub underflow")
-			.unwrap();
-		
-		<RewardTotal>::put(remainder as Value);
+	/// Verifies every `(sigscript, pubkey)` pair over `simple_transaction`. The crypto backend has
+	/// no batch call, so this falls back to one verification per distinct pair: inputs owned by the
+	/// same key carry the same signature and are checked only once.
+	pub fn batch_verify(simple_transaction: &[u8], pairs: &[(H512, H256)]) -> bool {
+		let distinct: BTreeMap<_, ()> = pairs.iter().map(|pair| (pair, ())).collect();
+		distinct.keys().all(|(sigscript, pubkey)| Self::signed_by(sigscript, simple_transaction, *pubkey))
+	}
 
-		// 2. create utxo per Validator
-		for authority in authorities {
-			let utxo = TransactionOutput {
-				value: share_value,
-				pubkey: *authority,
-			};
+	/// The message `owner` signs to let `authorized` spend its UTXOs. Like `get_simple_transaction`
+	/// it starts with the genesis block hash, so a grant can't be replayed on another chain.
+	pub fn spend_authority_payload(owner: H256, authorized: &[H256], nonce: u64) -> Vec<u8> {
+		let genesis_hash = <system::Module<T>>::block_hash(T::BlockNumber::zero());
+		(genesis_hash, b"spend_authority", owner, authorized, nonce).encode()
+	}
 
-			let hash = BlakeTwo256::hash_of(& (&utxo,
-				<system::Module<T>>::block_number().saturated_into::<u64>()));
-			
-			if <UtxoStore>::contains_key(hash) {
-				<UtxoStore>::insert(hash, utxo);
-				sp_runtime::print("Transaction reward sent to ");
-				sp_runtime::print(hash.as_fixed_bytes() as &[u8]);
-			} else {
-				sp_runtime::print("Transaction reward wasted due to a hash collistion");
-			}
-		}
+	/// `pubkey` may be an sr25519 or an ed25519 key; an sr25519 signature is tried first.
+	fn signed_by(sigscript: &H512, simple_transaction: &[u8], pubkey: H256) -> bool {
+		sp_io::crypto::sr25519_verify(
+			&Signature::from_raw(*sigscript.as_fixed_bytes()),
+			simple_transaction,
+			&Public::from_h256(pubkey)
+		) || sp_io::crypto::ed25519_verify(
+			&ed25519::Signature::from_raw(*sigscript.as_fixed_bytes()),
+			simple_transaction,
+			&ed25519::Public::from_raw(*pubkey.as_fixed_bytes())
+		)
 	}
 
-	pub fn validate_transaction(transaction: &Transaction) -> Result<ValidTransaction, &'static str> {
-		ensure!(!transaction.inputs.is_empty(), "No inputs");
-		ensure!(!transaction.outputs.is_empty(), "No outputs");
+	/// Builds the transaction `spend_with_auto_change` applies: `amount` to `recipient`, then the
+	/// change to `change_to` unless the inputs are spent exactly.
+	pub fn with_change(
+		inputs: Vec<TransactionInput>,
+		recipient: H256,
+		amount: Value,
+		change_to: H256,
+		fee: Value,
+		valid_until: u64,
+	) -> Result<Transaction, UtxoError> {
+		let mut total_input: Value = 0;
+		for input in &inputs {
+			let utxo = <UtxoStore>::get(&input.outpoint).ok_or(UtxoError::MissingInput)?;
+			total_input = total_input.checked_add(utxo.value).ok_or(UtxoError::InputValueOverflow)?;
+		}
+		let change = amount.checked_add(fee)
+			.and_then(|spent| total_input.checked_sub(spent))
+			.ok_or(UtxoError::OutputExceedsInput)?;
 
-		{
-			let input_set: BTreeMap<_, ()> = transaction.inputs.iter().map(|input| (input, ())).collect();
-			ensure!( input_set.len() == transaction.inputs.len(), "each input must only be used once");
+		let mut outputs = Vec::new();
+		outputs.push(TransactionOutput { value: amount, pubkey: recipient, lock: Lock::Pubkey, lock_until: None, relative_lock: 0 });
+		if change > 0 {
+			outputs.push(TransactionOutput { value: change, pubkey: change_to, lock: Lock::Pubkey, lock_until: None, relative_lock: 0 });
 		}
+		Ok(Transaction { inputs, outputs, valid_until, tip_to: None })
+	}
 
-		{
-			let output_set: BTreeMap<_, ()> = transaction.outputs.iter().map(|input| (input, ())).collect();
-			ensure!( output_set.len() == transaction.outputs.len(), "each output must only be used once");
+	/// Builds an unsigned transaction moving every UTXO of a compromised key into one output for
+	/// `safe_key`, less `fee`. `utxos` pairs each input with its value; only `OwnedOutpoints` is read,
+	/// to check that every input belongs to `compromised_key`, which still has to sign the result.
+	pub fn build_sweep(
+		compromised_key: H256,
+		safe_key: H256,
+		utxos: Vec<(TransactionInput, Value)>,
+		fee: Value,
+	) -> Result<Transaction, UtxoError> {
+		let owned = <OwnedOutpoints>::get(compromised_key);
+		let mut total: Value = 0;
+		for (input, value) in &utxos {
+			ensure!(owned.contains(&input.outpoint), UtxoError::NotOwner);
+			total = total.checked_add(*value).ok_or(UtxoError::InputValueOverflow)?;
 		}
+		let value = total.checked_sub(fee).ok_or(UtxoError::OutputExceedsInput)?;
 
-		//TODO: implement simple_transaction
-		let simple_transaction = Self::get_simple_transaction(transaction);
+		let mut outputs = Vec::new();
+		outputs.push(TransactionOutput { value, pubkey: safe_key, lock: Lock::Pubkey, lock_until: None, relative_lock: 0 });
+		Ok(Transaction {
+			inputs: utxos.into_iter().map(|(input, _)| input).collect(),
+			outputs,
+			valid_until: u64::MAX,
+			tip_to: None,
+		})
+	}
+
+	/// Builds the transaction `consolidate` applies: a single output to `dest` holding the inputs'
+	/// total less `fee`. Like any spend, the fee must meet both `MinimumFee` and the relay minimum.
+	pub fn consolidating(inputs: Vec<TransactionInput>, dest: H256, fee: Value) -> Result<Transaction, UtxoError> {
 		let mut total_input: Value = 0;
-		let mut total_output: Value = 0;
+		for input in &inputs {
+			let utxo = <UtxoStore>::get(&input.outpoint).ok_or(UtxoError::MissingInput)?;
+			total_input = total_input.checked_add(utxo.value).ok_or(UtxoError::InputValueOverflow)?;
+		}
+		let value = total_input.checked_sub(fee).ok_or(UtxoError::OutputExceedsInput)?;
 
-		let mut missing_utxos = Vec::new();
-		let mut new_utxos = Vec::new();
-		let mut reward = 0;
+		let mut outputs = Vec::new();
+		outputs.push(TransactionOutput { value, pubkey: dest, lock: Lock::Pubkey, lock_until: None, relative_lock: 0 });
+		Ok(Transaction { inputs, outputs, valid_until: u64::MAX, tip_to: None })
+	}
 
-		for input in transaction.inputs.iter() {
-			if let Some(input_utxo) = <UtxoStore>::get(&input.outpoint) {
-				ensure!( sp_io::crypto::sr25519_verify(
-					&Signature::from_raw(*input.sigscript.as_fixed_bytes()),
-					&simple_transaction,
-					&Public::from_h256(input_utxo.pubkey)
-				), "signature must be valid" );
-				total_input = total_input.checked_add(input_utxo.value).ok_or("input value overflow")?;	
-			} else {
-				//TODO
-				missing_utxos.push(input.outpoint.clone().as_fixed_bytes().to_vec());
-			}
-		}
+	/// The transaction whose signing payload `burn` checks `inputs` against.
+	pub fn burning(inputs: Vec<TransactionInput>) -> Transaction {
+		Transaction { inputs, ..Default::default() }
+	}
 
-		let mut output_index: u64 = 0;
-		for output in transaction.outputs.iter() {
-			ensure!(output.value > 0, "Output value must be nonzero");
-			let hash = BlakeTwo256::hash_of(&(&transaction.encode(), output_index));
-			output_index = output_index.checked_add(1).ok_or("output index overflow")?;
-			ensure!(! <UtxoStore>::contains_key(hash), "output already exists");
-			total_output = total_output.checked_add(output.value).ok_or("output value overflow")?;
-			new_utxos.push(hash.as_fixed_bytes().to_vec());
+	/// Checks that `burn` may destroy the UTXOs behind `inputs`, returning each input's owner and
+	/// their total value.
+	fn check_burn(inputs: &[TransactionInput]) -> Result<(Vec<H256>, Value), UtxoError> {
+		ensure!(!inputs.is_empty(), UtxoError::NoInputs);
+		ensure!(inputs.len() <= T::MaxInputs::get() as usize, UtxoError::TooManyInputs);
+		{
+			let outpoint_set: BTreeMap<_, ()> = inputs.iter().map(|input| (input.outpoint, ())).collect();
+			ensure!(outpoint_set.len() == inputs.len(), UtxoError::DuplicateInput);
 		}
 
-		if missing_utxos.is_empty() {
-			ensure!( total_input >= total_output, "output value mustr not exceed input value");
-			reward = total_input.checked_sub(total_output).ok_or("reward overflow")?;
+		let simple_transaction = Self::get_simple_transaction(&Self::burning(inputs.to_vec()));
+		let mut total: Value = 0;
+		let mut owners = Vec::new();
+		for input in inputs {
+			let utxo = <UtxoStore>::get(&input.outpoint).ok_or(UtxoError::MissingInput)?;
+			ensure!(!<Frozen>::get(utxo.pubkey), UtxoError::SpenderFrozen);
+			if T::VerifySignatures::get() {
+				Self::verify_input(input, &utxo, &simple_transaction)?;
+			}
+			total = total.checked_add(utxo.value).ok_or(UtxoError::InputValueOverflow)?;
+			owners.push(utxo.pubkey);
 		}
+		Ok((owners, total))
+	}
 
-		Ok(ValidTransaction {
-			requires: missing_utxos,
-			provides: new_utxos,
-			priority: reward as u64,
-			longevity: TransactionLongevity::max_value(),
-			propagate: true,
-		})
+	fn apply_transaction(transaction: Transaction) -> DispatchResult {
+		Self::structural_check(&transaction)?;
+		let inputs = Self::load_inputs(&transaction);
+		// the pool may hold a transaction until its inputs exist, but a block can't apply it sooner
+		ensure!(inputs.iter().all(|(_, utxo)| utxo.is_some()), UtxoError::MissingInput);
+		// every input is loaded, so this is the full check the pool may have skipped
+		Self::validate_loaded_transaction(&transaction, &inputs, true)?;
+
+		let fee = Self::fee_of(&transaction, &inputs)?;
+		let created = Self::update_storage(&transaction, &inputs, fee)?;
+
+		// 3. emit success event
+		Self::deposit_event(Event::TransactionSuccess(transaction, fee, created));
+
+		Ok(())
 	}
-}
 
+	/// `total_input - total_output` over the loaded inputs. Unlike the pool's `priority`, which is
+	/// zero while an input is missing and capped at `u64::MAX`, it is the fee actually paid.
+	fn fee_of(transaction: &Transaction, inputs: &[(H256, Option<TransactionOutput>)]) -> Result<Value, UtxoError> {
+		let total_input = inputs.iter()
+			.filter_map(|(_, utxo)| utxo.as_ref())
+			.try_fold(0 as Value, |total, utxo| total.checked_add(utxo.value))
+			.ok_or(UtxoError::InputValueOverflow)?;
+		let total_output = transaction.outputs.iter()
+			.try_fold(0 as Value, |total, output| total.checked_add(output.value))
+			.ok_or(UtxoError::OutputValueOverflow)?;
+		total_input.checked_sub(total_output).ok_or(UtxoError::OutputExceedsInput)
+	}
 
-/// Tests for this module
-#[cfg(test)]
-mod tests {
-	use super::*;
+	/// Validates every transaction against the UTXO set as the earlier ones would leave it, then
+	/// applies them all.
+	fn apply_batch(transactions: Vec<Transaction>) -> DispatchResult {
+		// outputs created, and outpoints spent, by the transactions validated so far
+		let mut pending: BTreeMap<H256, TransactionOutput> = BTreeMap::new();
+		let mut spent: BTreeMap<H256, ()> = BTreeMap::new();
+		let mut validated = Vec::new();
 
-	use frame_support::{assert_ok, assert_err, impl_outer_origin, parameter_types, weights::Weight};
-	use sp_runtime::{testing::Header, traits::IdentityLookup, Perbill};
-	use sp_core::testing::{KeyStore, SR25519};
-	use sp_core::traits::KeystoreExt;
+		for transaction in transactions {
+			Self::structural_check(&transaction)?;
+			let inputs: Vec<(H256, Option<TransactionOutput>)> = transaction.inputs.iter().map(|input| {
+				let utxo = if spent.contains_key(&input.outpoint) {
+					None
+				} else {
+					pending.get(&input.outpoint).cloned().or_else(|| <UtxoStore>::get(&input.outpoint))
+				};
+				(input.outpoint, utxo)
+			}).collect();
+			ensure!(inputs.iter().all(|(_, utxo)| utxo.is_some()), UtxoError::MissingInput);
+			// an output created in this block can't have waited out a relative lock
+			ensure!(
+				inputs.iter()
+					.filter(|(outpoint, _)| pending.contains_key(outpoint))
+					.all(|(_, utxo)| utxo.as_ref().map_or(true, |utxo| utxo.relative_lock == 0)),
+				UtxoError::RelativeLockNotReached
+			);
+			Self::validate_loaded_transaction(&transaction, &inputs, true)?;
+			let fee = Self::fee_of(&transaction, &inputs)?;
 
-	impl_outer_origin! {
-		pub enum Origin for Test {}
+			for (outpoint, _) in &inputs {
+				pending.remove(outpoint);
+				spent.insert(*outpoint, ());
+			}
+			for (index, output) in transaction.outputs.iter().enumerate().filter(|(_, output)| !output.lock.is_data()) {
+				pending.insert(BlakeTwo256::hash_of(&(&transaction.encode(), index as u64)), output.clone());
+			}
+			validated.push((transaction, inputs, fee));
+		}
+
+		for (transaction, inputs, fee) in validated {
+			let created = Self::update_storage(&transaction, &inputs, fee)?;
+			Self::deposit_event(Event::TransactionSuccess(transaction, fee, created));
+		}
+
+		Ok(())
 	}
 
-	#[derive(Clone, Eq, PartialEq)]
-	pub struct Test;
-	parameter_types! {
-			pub const BlockHashCount: u64 = 250;
-			pub const MaximumBlockWeight: Weight = 1024;
-			pub const MaximumBlockLength: u32 = 2 * 1024;
-			pub const AvailableBlockRatio: Perbill = Perbill::from_percent(75);
+	/// Checks the input's signature against the keys allowed to spend `utxo` at the current block:
+	/// its owner, the owner's `SpendAuthority` keys and a due escrow fallback, enough of a
+	/// multisig's keys, or an HTLC's claim or refund key.
+	fn verify_input(
+		input: &TransactionInput,
+		utxo: &TransactionOutput,
+		simple_transaction: &[u8],
+	) -> Result<(), UtxoError> {
+		let signed_by = |pubkey: H256| Self::signed_by(&input.sigscript, simple_transaction, pubkey);
+
+		if let Lock::MultiSig { threshold, keys } = &utxo.lock {
+			ensure!(input.witness.len() <= keys.len(), UtxoError::TooManySignatures);
+			// each signature counts for at most one key, and a matched key is not tried again
+			let mut unmatched: BTreeMap<_, ()> = keys.iter().map(|key| (*key, ())).collect();
+			let mut signed = 0;
+			for signature in sp_std::iter::once(&input.sigscript).chain(&input.witness) {
+				if signed >= *threshold as usize {
+					break;
+				}
+				let matched = unmatched.keys()
+					.find(|key| Self::signed_by(signature, simple_transaction, **key))
+					.copied();
+				if let Some(key) = matched {
+					unmatched.remove(&key);
+					signed += 1;
+				}
+			}
+			ensure!(signed >= *threshold as usize, UtxoError::MultiSigThresholdNotMet);
+			return Ok(());
+		}
+		if let Lock::Htlc { hashlock, timeout, claim_key, refund_key } = &utxo.lock {
+			let current_block = <system::Module<T>>::block_number().saturated_into::<u64>();
+			return if current_block <= *timeout && signed_by(*claim_key) {
+				let preimage = input.witness.first().ok_or(UtxoError::HtlcBadPreimage)?;
+				ensure!(BlakeTwo256::hash(preimage.as_bytes()) == *hashlock, UtxoError::HtlcBadPreimage);
+				Ok(())
+			} else if signed_by(*refund_key) {
+				ensure!(current_block > *timeout, UtxoError::HtlcTimeoutNotReached);
+				Ok(())
+			} else if signed_by(*claim_key) {
+				Err(UtxoError::HtlcExpired)
+			} else {
+				Err(UtxoError::BadSignature)
+			};
+		}
+		if signed_by(utxo.pubkey) || <SpendAuthority>::get(utxo.pubkey).into_iter().any(signed_by) {
+			return Ok(());
+		}
+		match utxo.lock {
+			Lock::Escrow { fallback, deadline } if signed_by(fallback) => {
+				let current_block = <system::Module<T>>::block_number().saturated_into::<u64>();
+				ensure!(current_block > deadline, UtxoError::EscrowDeadlineNotReached);
+				Ok(())
+			}
+			_ => Err(UtxoError::BadSignature),
+		}
 	}
-	impl system::Trait for Test {
-		type Origin = Origin;
+
+	fn update_storage(
+		transaction: &Transaction,
+		inputs: &[(H256, Option<TransactionOutput>)],
+		reward: Value,
+	) -> Result<Vec<H256>, DispatchError> {
+		// the treasury and tip UTXOs, which follow the transaction's own outputs
+		let mut paid_out = Vec::new();
+		let total_fees = <TotalFeesCollected>::get()
+			.checked_add(reward)
+			.ok_or("fee total overflow")?;
+		<BlockTransactions>::mutate(|count| *count = count.saturating_add(1));
+
+		// 0. Carve the treasury share off the reward into its own UTXO
+		let treasury_exact = reward
+			.checked_mul(T::TreasuryShare::get().deconstruct() as Value)
+			.and_then(|parts| parts.checked_add(<RewardDust>::get()))
+			.ok_or("reward overflow")?;
+		let treasury_value = treasury_exact / REWARD_PRECISION;
+		<RewardDust>::put(treasury_exact % REWARD_PRECISION);
+		let reward = reward - treasury_value;
+		if treasury_value > 0 {
+			let index = transaction.outputs.len() as u64;
+			let hash = BlakeTwo256::hash_of(&(&transaction.encode(), index));
+			Self::insert_utxo(hash, TransactionOutput {
+				value: treasury_value,
+				pubkey: <TreasuryKey>::get(),
+				lock: Lock::Pubkey,
+				lock_until: None,
+				relative_lock: 0,
+			});
+			paid_out.push(hash);
+		}
+
+		let tip_to = transaction.tip_to.filter(|key| Self::authorities().contains(key));
+		if let Some(authority) = tip_to {
+			if reward > 0 {
+				let index = transaction.outputs.len() as u64 + 1;
+				let hash = BlakeTwo256::hash_of(&(&transaction.encode(), index));
+				Self::insert_utxo(hash, TransactionOutput {
+					value: reward,
+					pubkey: authority,
+					lock: Lock::Pubkey,
+					lock_until: None,
+					relative_lock: 0,
+				});
+				<RewardMaturity>::insert(hash, <system::Module<T>>::block_number().saturated_into::<u64>());
+				paid_out.push(hash);
+			}
+		} else {
+			let new_total: Value = <RewardTotal>::get()
+				.checked_add(reward)
+				.ok_or("reward overflow")?;
+			<RewardTotal>::put(new_total);
+		}
+		<TotalFeesCollected>::put(total_fees);
+
+		// 1. Remove UTXO from utxoStrore, using the UTXOs validation already loaded
+		let mut spent_by_owner: BTreeMap<H256, Vec<H256>> = BTreeMap::new();
+		for (outpoint, utxo) in inputs {
+			if let Some(utxo) = utxo {
+				spent_by_owner.entry(utxo.pubkey).or_default().push(*outpoint);
+			}
+		}
+		for (owner, outpoints) in spent_by_owner {
+			Self::remove_utxos(owner, &outpoints);
+		}
+		// 2. Create new UTXOs in utxostore
+		let mut created = Vec::new();
+		let mut index: u64 = 0; 
+		for output in &transaction.outputs {
+			let hash = BlakeTwo256::hash_of(&(&transaction.encode(), index));
+			index = index.checked_add(1).ok_or("output index overflow")?;
+			if output.lock.is_data() {
+				continue;
+			}
+			Self::insert_utxo(hash, output.clone());
+			created.push(hash);
+		}
+		created.extend(paid_out);
+		Ok(created)
+	}
+
+	/// Stores `utxo` at `hash`, indexes it under its owner in `OwnedOutpoints` and, if it is
+	/// relatively locked, records its `CreatedAt` block.
+	fn insert_utxo(hash: H256, utxo: TransactionOutput) {
+		if utxo.relative_lock > 0 {
+			<CreatedAt>::insert(hash, <system::Module<T>>::block_number().saturated_into::<u64>());
+		}
+		<OwnedOutpoints>::append_or_insert(utxo.pubkey, &[hash][..]);
+		<UtxoStore>::insert(hash, utxo);
+	}
+
+	/// Removes the UTXO at `outpoint`, owned by `owner`, along with everything kept about it.
+	fn remove_utxo(outpoint: &H256, owner: H256) {
+		Self::remove_utxos(owner, &[*outpoint]);
+	}
+
+	/// Removes several UTXOs of one owner, reading and writing its `OwnedOutpoints` entry once.
+	fn remove_utxos(owner: H256, outpoints: &[H256]) {
+		#[cfg(test)]
+		tests::OWNED_OUTPOINTS_UPDATES.with(|updates| updates.set(updates.get() + 1));
+
+		for outpoint in outpoints {
+			<UtxoStore>::remove(outpoint);
+			<RewardMaturity>::remove(outpoint);
+			<CreatedAt>::remove(outpoint);
+		}
+		<OwnedOutpoints>::mutate_exists(owner, |owned| {
+			if let Some(owned_outpoints) = owned {
+				owned_outpoints.retain(|owned| !outpoints.contains(owned));
+				if owned_outpoints.is_empty() {
+					*owned = None;
+				}
+			}
+		});
+	}
+
+	/// Every UTXO `pubkey` owns, read through `OwnedOutpoints` rather than a scan of `UtxoStore`.
+	pub fn utxos_of(pubkey: H256) -> Vec<(H256, TransactionOutput)> {
+		<OwnedOutpoints>::get(pubkey)
+			.into_iter()
+			.filter_map(|outpoint| <UtxoStore>::get(outpoint).map(|utxo| (outpoint, utxo)))
+			.collect()
+	}
+
+	/// Brings storage written by an older runtime up to `STORAGE_VERSION`.
+	fn migrate_utxo_store() {
+		let version = <StorageVersion>::get();
+		if version >= STORAGE_VERSION {
+			return
+		}
+		if version < 1 {
+			// UTXOs stored before `lock_until` existed are not time-locked
+			<UtxoStore>::translate(|_, old: TransactionOutputV0| Some(TransactionOutput {
+				value: old.value,
+				pubkey: old.pubkey,
+				lock: old.lock,
+				lock_until: None,
+				relative_lock: 0,
+			}));
+		} else if version < 4 {
+			<UtxoStore>::translate(|_, old: TransactionOutputV1| Some(TransactionOutput {
+				value: old.value,
+				pubkey: old.pubkey,
+				lock: old.lock,
+				lock_until: old.lock_until,
+				relative_lock: 0,
+			}));
+		}
+		if version < 2 {
+			for (hash, utxo) in <UtxoStore>::iter() {
+				<OwnedOutpoints>::append_or_insert(utxo.pubkey, &[hash][..]);
+			}
+		}
+		if version < 3 {
+			<TotalSupply>::put(Self::unspent_value().saturating_add(<RewardTotal>::get()));
+		}
+		<StorageVersion>::put(STORAGE_VERSION);
+	}
+
+	/// The current Aura authorities' sr25519 keys, converted again only when the set changed.
+	fn authorities() -> Vec<H256> {
+		let aura_authorities = Aura::authorities();
+		let set_hash = BlakeTwo256::hash_of(&aura_authorities);
+		if set_hash == <AuthoritiesHash>::get() {
+			return <CachedAuthorities>::get();
+		}
+
+		#[cfg(test)]
+		tests::AUTHORITY_REBUILDS.with(|rebuilds| rebuilds.set(rebuilds.get() + 1));
+
+		let authorities: Vec<H256> = aura_authorities.iter().map(|x| {
+			let r: &Public = x.as_ref();
+			r.0.into()
+		}).collect();
+		<CachedAuthorities>::put(&authorities);
+		<AuthoritiesHash>::put(set_hash);
+		authorities
+	}
+
+	/// Greedily picks `pubkey`'s largest UTXOs until they cover `target`, or `None` if its
+	/// spendable balance is not enough. Only plain outputs `can_spend` lets it use right now
+	/// are considered, so a signature by `pubkey` is all the selected inputs need.
+	pub fn select_inputs(pubkey: H256, target: Value) -> Option<Vec<(H256, Value)>> {
+		let current_block = <system::Module<T>>::block_number().saturated_into::<u64>();
+		let mut owned: Vec<(H256, Value)> = Self::utxos_of(pubkey)
+			.into_iter()
+			.filter(|(outpoint, utxo)| {
+				utxo.lock == Lock::Pubkey && Self::can_spend(*outpoint, pubkey, current_block).is_ok()
+			})
+			.map(|(outpoint, utxo)| (outpoint, utxo.value))
+			.collect();
+		owned.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+		let mut selected = Vec::new();
+		let mut total: Value = 0;
+		for (outpoint, value) in owned {
+			if total >= target {
+				break;
+			}
+			total = total.saturating_add(value);
+			selected.push((outpoint, value));
+		}
+
+		if total >= target { Some(selected) } else { None }
+	}
+
+	/// Whether `new` may replace `old` in the transaction pool: both must spend at least one
+	/// common UTXO and `new` must pay a strictly higher reward.
+	pub fn can_replace(old: &Transaction, new: &Transaction) -> bool {
+		let shares_input = new.inputs.iter()
+			.any(|input| old.inputs.iter().any(|old_input| old_input.outpoint == input.outpoint));
+		shares_input && Self::transaction_reward(new) > Self::transaction_reward(old)
+	}
+
+	fn transaction_reward(transaction: &Transaction) -> Value {
+		let total_input: Value = transaction.inputs.iter()
+			.filter_map(|input| <UtxoStore>::get(&input.outpoint))
+			.fold(0, |total, utxo| total.saturating_add(utxo.value));
+		let total_output: Value = transaction.outputs.iter()
+			.fold(0, |total, output| total.saturating_add(output.value));
+		total_input.saturating_sub(total_output)
+	}
+
+	fn spend_tag(outpoint: &H256) -> Vec<u8> {
+		(b"spend", outpoint).encode()
+	}
+
+	pub fn utxo_count() -> u32 {
+		<UtxoStore>::iter().count() as u32
+	}
+
+	/// Total value held in unspent outputs, not counting the undispersed reward pool.
+	pub fn unspent_value() -> Value {
+		<UtxoStore>::iter().fold(0, |total: Value, (_, utxo)| total.saturating_add(utxo.value))
+	}
+
+	/// Dashboard statistics in one call: `(utxo_count, unspent_value, reward_total)`.
+	pub fn set_summary() -> (u32, Value, Value) {
+		(Self::utxo_count(), Self::unspent_value(), <RewardTotal>::get())
+	}
+
+	/// Value the treasury holds: its `TreasuryShare` cuts of every fee so far, less what it spent.
+	pub fn treasury_balance() -> Value {
+		Self::utxos_of(<TreasuryKey>::get()).iter()
+			.fold(0, |total: Value, (_, utxo)| total.saturating_add(utxo.value))
+	}
+
+	/// Checks that no value was created or lost: every UTXO plus `RewardTotal` adds up to
+	/// `TotalSupply`. Scans the whole store, so it is meant for tests and offchain audits.
+	pub fn ensure_supply_invariant() -> Result<(), UtxoError> {
+		let held = Self::unspent_value().checked_add(<RewardTotal>::get()).ok_or(UtxoError::SupplyMismatch)?;
+		ensure!(held == <TotalSupply>::get(), UtxoError::SupplyMismatch);
+		Ok(())
+	}
+
+	/// What is left of `reward` once each of `authorities` is paid `share_value`, or `None` if
+	/// those shares would exceed it.
+	fn dispersal_remainder(reward: Value, share_value: Value, authorities: usize) -> Option<Value> {
+		share_value
+			.checked_mul(authorities as Value)
+			.and_then(|paid| reward.checked_sub(paid))
+	}
+
+	/// Projects the per-authority shares and the remainder that would carry over, without
+	/// touching storage.
+	pub fn preview_dispersal(authorities: &[H256]) -> (Vec<(H256, Value)>, Value) {
+		let reward = <RewardTotal>::get();
+		let share_value: Value = match reward.checked_div(authorities.len() as Value) {
+			Some(share_value) if share_value > 0 => share_value,
+			_ => return (Vec::new(), reward),
+		};
+		let remainder = match Self::dispersal_remainder(reward, share_value, authorities.len()) {
+			Some(remainder) => remainder,
+			None => return (Vec::new(), reward),
+		};
+
+		let shares = authorities.iter().map(|authority| (*authority, share_value)).collect();
+		(shares, remainder)
+	}
+
+	/// How many times `MinimumFee` a transaction must pay: one, plus one for each full
+	/// `CongestionTarget` of transactions per block over the recent blocks.
+	pub fn congestion_multiplier() -> u64 {
+		let target = T::CongestionTarget::get() as u64;
+		if target == 0 {
+			return 1;
+		}
+		let recent = <RecentTransactions>::get()
+			.iter()
+			.fold(0, |total: u64, count| total.saturating_add(*count as u64));
+		1u64.saturating_add(recent / target.saturating_mul(CONGESTION_WINDOW as u64))
+	}
+
+	/// Moves the block's transaction count into `RecentTransactions`, dropping the oldest.
+	fn record_block_transactions() {
+		let mut recent = <RecentTransactions>::get();
+		recent.push(<BlockTransactions>::take());
+		if recent.len() > CONGESTION_WINDOW {
+			recent.remove(0);
+		}
+		<RecentTransactions>::put(recent);
+	}
+
+	fn disperse_rewards(authorities: &[H256]) {
+		// With no authorities the reward carries over to the next block
+		if authorities.is_empty() { return }
+
+		// 1. divide rewards fairly
+		let reward = <RewardTotal>::get();
+		let share_value: Value = reward / authorities.len() as Value;
+		
+		// too small to split, so it waits for more fees
+		if share_value == 0 { return }
+
+		// kept in the pool, so the next block's split includes it
+		let remainder = match Self::dispersal_remainder(reward, share_value, authorities.len()) {
+			Some(remainder) => remainder,
+			None => return,
+		};
+		<RewardTotal>::put(remainder);
+
+		// 2. create utxo per Validator
+		let block_number = <system::Module<T>>::block_number().saturated_into::<u64>();
+		let mut rewarded = Vec::new();
+		for authority in authorities {
+			let utxo = TransactionOutput {
+				value: share_value,
+				pubkey: *authority,
+				lock: Lock::Pubkey,
+				lock_until: None,
+				relative_lock: 0,
+			};
+
+			let hash = BlakeTwo256::hash_of(& (&utxo, block_number));
+			
+			if !<UtxoStore>::contains_key(hash) {
+				Self::insert_utxo(hash, utxo);
+				<RewardMaturity>::insert(hash, block_number);
+				rewarded.push(*authority);
+				sp_runtime::print("Transaction reward sent to ");
+				sp_runtime::print(hash.as_fixed_bytes() as &[u8]);
+			} else {
+				// keep the share in the pool rather than destroying it
+				<RewardTotal>::mutate(|total| *total = total.saturating_add(share_value));
+				sp_runtime::print("Transaction reward carried over due to a hash collision");
+			}
+		}
+
+		if !rewarded.is_empty() {
+			Self::deposit_event(Event::RewardDispersed(block_number, share_value, rewarded));
+		}
+	}
+
+	/// Cheap checks on the shape of a transaction, done before any storage reads or crypto.
+	fn structural_check(transaction: &Transaction) -> Result<(), UtxoError> {
+		ensure!(!transaction.inputs.is_empty(), UtxoError::NoInputs);
+		ensure!(!transaction.outputs.is_empty(), UtxoError::NoOutputs);
+		ensure!(transaction.inputs.len() <= T::MaxInputs::get() as usize, UtxoError::TooManyInputs);
+		ensure!(transaction.outputs.len() <= T::MaxOutputs::get() as usize, UtxoError::TooManyOutputs);
+
+		ensure!(
+			transaction.inputs.iter().all(|input| input.witness.len() <= T::MaxMultiSigKeys::get() as usize),
+			UtxoError::TooManySignatures
+		);
+
+		{
+			// keyed on the outpoint alone: the same UTXO signed twice must not count twice
+			let outpoint_set: BTreeMap<_, ()> = transaction.inputs.iter().map(|input| (input.outpoint, ())).collect();
+			ensure!(outpoint_set.len() == transaction.inputs.len(), UtxoError::DuplicateInput);
+		}
+
+		{
+			let output_set: BTreeMap<_, ()> = transaction.outputs.iter().map(|input| (input, ())).collect();
+			ensure!( output_set.len() == transaction.outputs.len(), UtxoError::DuplicateOutput);
+		}
+
+		for output in transaction.outputs.iter() {
+			if let Lock::Data { data } = &output.lock {
+				ensure!(
+					output.value == 0 && data.len() <= T::MaxDataLength::get() as usize,
+					UtxoError::InvalidDataOutput
+				);
+				continue;
+			}
+			ensure!(output.value > 0, UtxoError::OutputValueZero);
+			ensure!(output.value >= T::DustThreshold::get(), UtxoError::OutputBelowDustThreshold);
+			ensure!(output.lock.is_well_formed(T::MaxMultiSigKeys::get()), UtxoError::InvalidMultiSig);
+		}
+
+		if T::StrictOutputOrdering::get() {
+			ensure!(
+				transaction.outputs.windows(2).all(|pair| (pair[0].pubkey, pair[0].value) <= (pair[1].pubkey, pair[1].value)),
+				UtxoError::OutputsNotCanonical
+			);
+		}
+		Ok(())
+	}
+
+	/// Reads the UTXO behind every input exactly once, `None` for the ones not in the store yet.
+	fn load_inputs(transaction: &Transaction) -> Vec<(H256, Option<TransactionOutput>)> {
+		transaction.inputs.iter().map(|input| {
+			#[cfg(test)]
+			tests::INPUT_READS.with(|reads| reads.borrow_mut().push(input.outpoint));
+
+			(input.outpoint, <UtxoStore>::get(&input.outpoint))
+		}).collect()
+	}
+
+	/// What each input of `transaction` consumes, `None` where the UTXO is spent or doesn't exist
+	/// yet. For explorers.
+	pub fn trace_inputs(transaction: &Transaction) -> Vec<Option<TransactionOutput>> {
+		Self::load_inputs(transaction).into_iter().map(|(_, utxo)| utxo).collect()
+	}
+
+	pub fn validate_transaction(transaction: &Transaction) -> Result<ValidTransaction, UtxoError> {
+		Self::structural_check(transaction)?;
+		let inputs = Self::load_inputs(transaction);
+		Self::validate_loaded_transaction(transaction, &inputs, true)
+	}
+
+	/// Pool validity of a `batch_spend`: the transactions' tags merged, less the outputs a later
+	/// transaction requires from an earlier one. Two transactions spending the same outpoint make
+	/// the whole batch invalid.
+	pub fn validate_batch(transactions: &[Transaction]) -> Result<ValidTransaction, UtxoError> {
+		let mut requires = Vec::new();
+		let mut provides: BTreeMap<Vec<u8>, ()> = BTreeMap::new();
+		let mut priority: u64 = 0;
+		let mut longevity = TransactionLongevity::max_value();
+		for transaction in transactions {
+			let valid = Self::validate_transaction(transaction)?;
+			for tag in valid.provides {
+				ensure!(provides.insert(tag, ()).is_none(), UtxoError::DuplicateInput);
+			}
+			requires.extend(valid.requires);
+			priority = priority.saturating_add(valid.priority);
+			longevity = longevity.min(valid.longevity);
+		}
+		requires.retain(|tag| !provides.contains_key(tag));
+
+		Ok(ValidTransaction {
+			requires,
+			provides: provides.into_iter().map(|(tag, _)| tag).collect(),
+			priority,
+			longevity,
+			propagate: true,
+		})
+	}
+
+	/// Pool validity of a `burn`: it pays no reward, so it only claims the spend tags of `inputs`.
+	pub fn validate_burn(inputs: &[TransactionInput]) -> Result<ValidTransaction, UtxoError> {
+		Self::check_burn(inputs)?;
+		Ok(ValidTransaction {
+			requires: Vec::new(),
+			provides: inputs.iter().map(|input| Self::spend_tag(&input.outpoint)).collect(),
+			priority: 0,
+			longevity: TransactionLongevity::max_value(),
+			propagate: true,
+		})
+	}
+
+	/// With `conserve_value` unset the outputs may exceed the inputs, and the priority is 0.
+	fn validate_loaded_transaction(
+		transaction: &Transaction,
+		inputs: &[(H256, Option<TransactionOutput>)],
+		conserve_value: bool,
+	) -> Result<ValidTransaction, UtxoError> {
+		let current_block = <system::Module<T>>::block_number().saturated_into::<u64>();
+		ensure!(current_block <= transaction.valid_until, UtxoError::TransactionExpired);
+
+		//TODO: implement simple_transaction
+		let simple_transaction = Self::get_simple_transaction(transaction);
+		let mut total_input: Value = 0;
+		let mut total_output: Value = 0;
+
+		let mut missing_utxos = Vec::new();
+		let mut new_utxos = Vec::new();
+		let mut reward = 0;
+
+		// plain pubkey inputs are verified in one batch; escrows, and every input of a failing batch,
+		// go through `verify_input` one by one
+		let batch_verified = T::VerifySignatures::get() && Self::batch_verify(
+			&simple_transaction,
+			&transaction.inputs.iter().zip(inputs).filter_map(|(input, (_, loaded))| match loaded {
+				Some(utxo) if utxo.lock == Lock::Pubkey => Some((input.sigscript, utxo.pubkey)),
+				_ => None,
+			}).collect::<Vec<_>>(),
+		);
+
+		for (input, (_, loaded)) in transaction.inputs.iter().zip(inputs) {
+			if let Some(input_utxo) = loaded {
+				Self::ensure_unlocked(&input.outpoint, input_utxo, current_block)?;
+				if T::VerifySignatures::get() && !(batch_verified && input_utxo.lock == Lock::Pubkey) {
+					Self::verify_input(input, input_utxo, &simple_transaction)?;
+				}
+				total_input = total_input.checked_add(input_utxo.value).ok_or(UtxoError::InputValueOverflow)?;
+			} else {
+				// the outpoint is still pending: require the tag its creating transaction provides.
+				// Existing outpoints can't be required, as nothing in the pool provides them.
+				missing_utxos.push(input.outpoint.clone().as_fixed_bytes().to_vec());
+			}
+			// two pooled transactions spending the same outpoint, existing or pending, conflict on
+			// this tag, so the pool keeps the one with the higher priority (see `can_replace`)
+			new_utxos.push(Self::spend_tag(&input.outpoint));
+		}
+
+		let mut output_index: u64 = 0;
+		for output in transaction.outputs.iter() {
+			let hash = BlakeTwo256::hash_of(&(&transaction.encode(), output_index));
+			output_index = output_index.checked_add(1).ok_or(UtxoError::OutputIndexOverflow)?;
+			if output.lock.is_data() {
+				continue;
+			}
+			ensure!(! <UtxoStore>::contains_key(hash), UtxoError::OutputAlreadyExists);
+			ensure!(!<Frozen>::get(output.pubkey), UtxoError::RecipientFrozen);
+			total_output = total_output.checked_add(output.value).ok_or(UtxoError::OutputValueOverflow)?;
+			new_utxos.push(hash.as_fixed_bytes().to_vec());
+		}
+
+		if missing_utxos.is_empty() && conserve_value {
+			ensure!( total_input >= total_output, UtxoError::OutputExceedsInput);
+			reward = total_input.checked_sub(total_output).ok_or(UtxoError::RewardOverflow)?;
+			let minimum_fee = T::MinimumFee::get().saturating_mul(Self::congestion_multiplier() as Value);
+			let relay_fee = (transaction.encode().len() as Value).saturating_mul(T::MinRelayFeePerByte::get());
+			ensure!(reward >= minimum_fee, UtxoError::FeeTooLow);
+			ensure!(reward >= relay_fee, UtxoError::FeeBelowRelayMinimum);
+			// a data-only transaction has no output value to measure its fee against
+			ensure!(
+				reward <= total_output.max(minimum_fee).max(relay_fee).saturating_mul(T::MaxFeeRatio::get()),
+				UtxoError::ExcessiveFee
+			);
+		}
+
+		Ok(ValidTransaction {
+			requires: missing_utxos,
+			provides: new_utxos,
+			priority: reward as u64,
+			// the pool drops it once it can no longer be included
+			longevity: (transaction.valid_until - current_block).saturating_add(1),
+			propagate: true,
+		})
+	}
+}
+
+
+/// Tests for this module
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use frame_support::{assert_ok, assert_err, impl_outer_event, impl_outer_origin, parameter_types, weights::Weight};
+	use sp_runtime::{testing::Header, traits::IdentityLookup};
+	use sp_core::testing::{KeyStore, ED25519, SR25519};
+	use sp_core::traits::KeystoreExt;
+	use std::cell::{Cell, RefCell};
+
+	impl_outer_origin! {
+		pub enum Origin for Test {}
+	}
+
+	mod utxo {
+		pub use super::super::Event;
+	}
+
+	impl_outer_event! {
+		pub enum TestEvent for Test {
+			system<T>,
+			utxo,
+		}
+	}
+
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct Test;
+	parameter_types! {
+			pub const BlockHashCount: u64 = 250;
+			pub const MaximumBlockWeight: Weight = 1024;
+			pub const MaximumBlockLength: u32 = 2 * 1024;
+			pub const AvailableBlockRatio: Perbill = Perbill::from_percent(75);
+	}
+	impl system::Trait for Test {
+		type Origin = Origin;
 		type Call = ();
 		type Index = u64;
 		type BlockNumber = u64;
@@ -251,7 +1600,7 @@ mod tests {
 		type AccountId = u64;
 		type Lookup = IdentityLookup<Self::AccountId>;
 		type Header = Header;
-		type Event = ();
+		type Event = TestEvent;
 		type BlockHashCount = BlockHashCount;
 		type MaximumBlockWeight = MaximumBlockWeight;
 		type MaximumBlockLength = MaximumBlockLength;
@@ -262,8 +1611,80 @@ mod tests {
 		type OnNewAccount = ();
 		type OnKilledAccount = ();
 	}
+
+	thread_local! {
+		static VERIFY_SIGNATURES: RefCell<bool> = RefCell::new(true);
+		static STRICT_OUTPUT_ORDERING: RefCell<bool> = RefCell::new(false);
+		static MINIMUM_FEE: RefCell<Value> = RefCell::new(0);
+		static MIN_RELAY_FEE_PER_BYTE: RefCell<Value> = RefCell::new(0);
+		static TREASURY_SHARE: RefCell<Perbill> = RefCell::new(Perbill::from_percent(10));
+		pub static SIMPLE_TRANSACTION_CALLS: Cell<u32> = Cell::new(0);
+		pub static AUTHORITY_REBUILDS: Cell<u32> = Cell::new(0);
+		pub static INPUT_READS: RefCell<Vec<H256>> = RefCell::new(Vec::new());
+		pub static OWNED_OUTPOINTS_UPDATES: Cell<u32> = Cell::new(0);
+	}
+
+	pub struct VerifySignatures;
+	impl Get<bool> for VerifySignatures {
+		fn get() -> bool {
+			VERIFY_SIGNATURES.with(|v| *v.borrow())
+		}
+	}
+
+	pub struct StrictOutputOrdering;
+	impl Get<bool> for StrictOutputOrdering {
+		fn get() -> bool {
+			STRICT_OUTPUT_ORDERING.with(|v| *v.borrow())
+		}
+	}
+
+	pub struct TreasuryShare;
+	impl Get<Perbill> for TreasuryShare {
+		fn get() -> Perbill {
+			TREASURY_SHARE.with(|v| *v.borrow())
+		}
+	}
+
+	pub struct MinimumFee;
+	impl Get<Value> for MinimumFee {
+		fn get() -> Value {
+			MINIMUM_FEE.with(|v| *v.borrow())
+		}
+	}
+
+	pub struct MinRelayFeePerByte;
+	impl Get<Value> for MinRelayFeePerByte {
+		fn get() -> Value {
+			MIN_RELAY_FEE_PER_BYTE.with(|v| *v.borrow())
+		}
+	}
+
+	parameter_types! {
+		pub const MaxFeeRatio: Value = 10;
+		pub const MaturityPeriod: u64 = 3;
+		pub const DustThreshold: Value = 5;
+		pub const MaxDataLength: u32 = 8;
+		pub const CongestionTarget: u32 = 2;
+		pub const MaxInputs: u32 = 64;
+		pub const MaxOutputs: u32 = 16;
+		pub const MaxMultiSigKeys: u32 = 4;
+	}
+
 	impl Trait for Test {
-		type Event = ();
+		type Event = TestEvent;
+		type VerifySignatures = VerifySignatures;
+		type TreasuryShare = TreasuryShare;
+		type StrictOutputOrdering = StrictOutputOrdering;
+		type MaxFeeRatio = MaxFeeRatio;
+		type MinimumFee = MinimumFee;
+		type MaturityPeriod = MaturityPeriod;
+		type DustThreshold = DustThreshold;
+		type MinRelayFeePerByte = MinRelayFeePerByte;
+		type MaxDataLength = MaxDataLength;
+		type CongestionTarget = CongestionTarget;
+		type MaxInputs = MaxInputs;
+		type MaxOutputs = MaxOutputs;
+		type MaxMultiSigKeys = MaxMultiSigKeys;
 	}
 	
 	type Utxo = Module<Test>;
@@ -274,9 +1695,17 @@ mod tests {
 	const ALICE_PHRASE: &str = "news slush supreme milk chapter athlete soap sausage put clutch what kitten";
 	// other random account generated with subkey
 	const KARL_PHRASE: &str = "monitor exhibit resource stumble subject nut valid furnace obscure misery satoshi assume";
-	const GENESIS_UTXO: [u8; 32] = hex!("79eabcbd5ef6e958c6a7851b36da07691c19bda1835a08f875aa286911800999");
+	const GENESIS_UTXO: [u8; 32] = hex!("01a9fbfde6f0584e88dcff57b5cf4fc9d538bca4e91b1945437b325af7860b3a");
+	const TREASURY: [u8; 32] = [9; 32];
 
 	fn new_test_ext() -> sp_io::TestExternalities {
+		// tests share a thread, so toggles left over by an earlier test are reset
+		VERIFY_SIGNATURES.with(|v| *v.borrow_mut() = true);
+		STRICT_OUTPUT_ORDERING.with(|v| *v.borrow_mut() = false);
+		MINIMUM_FEE.with(|v| *v.borrow_mut() = 0);
+		MIN_RELAY_FEE_PER_BYTE.with(|v| *v.borrow_mut() = 0);
+		TREASURY_SHARE.with(|v| *v.borrow_mut() = Perbill::from_percent(10));
+
 		// 1. create keys for a test user : Alice
 		let keystore = KeyStore::new();
 		let alice_pub_key = keystore.write().sr25519_generate_new(SR25519, Some(ALICE_PHRASE)).unwrap();
@@ -289,11 +1718,9 @@ mod tests {
 		t.top.extend(
 			GenesisConfig {
 				genesis_utxos: vec! [
-					TransactionOutput {
-						value: 100,
-						pubkey: H256::from(alice_pub_key),
-					}
+					output(100, H256::from(alice_pub_key))
 				],
+				treasury_key: H256::from(TREASURY),
 				..Default::default()
 			}
 			.build_storage()
@@ -307,23 +1734,39 @@ mod tests {
 		ext
 	}
 
+	// test config that trusts inputs without checking their signatures
+	fn new_test_ext_without_signatures() -> sp_io::TestExternalities {
+		let ext = new_test_ext();
+		VERIFY_SIGNATURES.with(|v| *v.borrow_mut() = false);
+		ext
+	}
+
+	// a plain output to `pubkey`, without any lock
+	fn output(value: Value, pubkey: H256) -> TransactionOutput {
+		TransactionOutput { value, pubkey, lock: Lock::Pubkey, lock_until: None, relative_lock: 0 }
+	}
+
+	// an unsigned input spending `outpoint`
+	fn input(outpoint: H256) -> TransactionInput {
+		TransactionInput { outpoint, sigscript: H512::zero(), witness: Vec::new() }
+	}
+
+	// a transaction that never expires and tips no one
+	fn paying(inputs: Vec<TransactionInput>, outputs: Vec<TransactionOutput>) -> Transaction {
+		Transaction { inputs, outputs, valid_until: u64::MAX, tip_to: None }
+	}
+
 	#[test]
 	fn test_simple_transaction() {
 		new_test_ext().execute_with(|| {
 			let alice_pub_key = sp_io::crypto::sr25519_public_keys(SR25519)[0];
 
-			let mut transaction = Transaction {
-				inputs: vec![TransactionInput {
-					outpoint: H256::from(GENESIS_UTXO),
-					sigscript: H512::zero(),
-				}],
-				outputs: vec![TransactionOutput {
-					value: 50,
-					pubkey: H256::from(alice_pub_key),
-				}],
-			};
+			let mut transaction = paying(
+				vec![input(H256::from(GENESIS_UTXO))],
+				vec![output(50, H256::from(alice_pub_key))],
+			);
 
-			let alice_signature = sp_io::crypto::sr25519_sign(SR25519, &alice_pub_key, &transaction.encode()).unwrap();
+			let alice_signature = sp_io::crypto::sr25519_sign(SR25519, &alice_pub_key, &Utxo::get_simple_transaction(&transaction)).unwrap();
 			transaction.inputs[0].sigscript = H512::from(alice_signature);
 			let new_utxo_hash = BlakeTwo256::hash_of(&(&transaction.encode(), 0 as u64));
 
@@ -336,4 +1779,1875 @@ mod tests {
 			assert_eq!(50, UtxoStore::get(new_utxo_hash).unwrap().value);
 		});
 	}
+	#[test]
+	fn test_spend_without_signature_verification() {
+		new_test_ext_without_signatures().execute_with(|| {
+			let alice_pub_key = sp_io::crypto::sr25519_public_keys(SR25519)[0];
+
+			let transaction = paying(
+				vec![input(H256::from(GENESIS_UTXO))],
+				vec![output(50, H256::from(alice_pub_key))],
+			);
+
+			assert_ok!(Utxo::spend(Origin::signed(0), transaction));
+			assert!(! UtxoStore::contains_key(H256::from(GENESIS_UTXO)));
+		});
+	}
+
+	#[test]
+	fn test_empty_sigscript_is_rejected_when_verifying() {
+		new_test_ext().execute_with(|| {
+			let alice_pub_key = sp_io::crypto::sr25519_public_keys(SR25519)[0];
+
+			let transaction = paying(
+				vec![input(H256::from(GENESIS_UTXO))],
+				vec![output(50, H256::from(alice_pub_key))],
+			);
+
+			assert_err!(Utxo::spend(Origin::signed(0), transaction), "signature must be valid");
+		});
+	}
+	#[test]
+	fn test_can_spend() {
+		new_test_ext().execute_with(|| {
+			let alice_pub_key = sp_io::crypto::sr25519_public_keys(SR25519)[0];
+
+			assert_ok!(Utxo::can_spend(H256::from(GENESIS_UTXO), H256::from(alice_pub_key), 0));
+			assert_err!(
+				Utxo::can_spend(H256::zero(), H256::from(alice_pub_key), 0),
+				UtxoError::MissingInput
+			);
+			assert_err!(
+				Utxo::can_spend(H256::from(GENESIS_UTXO), H256::zero(), 0),
+				UtxoError::NotOwner
+			);
+		});
+	}
+	#[test]
+	fn test_preview_dispersal() {
+		new_test_ext().execute_with(|| {
+			let authorities = vec![H256::repeat_byte(1), H256::repeat_byte(2), H256::repeat_byte(3)];
+			RewardTotal::put(10);
+
+			let (shares, remainder) = Utxo::preview_dispersal(&authorities);
+			assert_eq!(shares, vec![
+				(H256::repeat_byte(1), 3),
+				(H256::repeat_byte(2), 3),
+				(H256::repeat_byte(3), 3),
+			]);
+			assert_eq!(remainder, 1);
+			// previewing leaves the reward pool untouched
+			assert_eq!(Utxo::reward_total(), 10);
+
+			Utxo::disperse_rewards(&authorities);
+			assert_eq!(Utxo::reward_total(), remainder);
+		});
+	}
+	fn seed_utxos(pubkey: H256, values: &[Value]) {
+		for (index, value) in values.iter().enumerate() {
+			Utxo::insert_utxo(H256::repeat_byte(index as u8 + 1), output(*value, pubkey));
+			TotalSupply::mutate(|supply| *supply = supply.saturating_add(*value));
+		}
+	}
+
+	#[test]
+	fn test_select_inputs_across_utxos() {
+		new_test_ext().execute_with(|| {
+			let karl = H256::repeat_byte(7);
+			seed_utxos(karl, &[10, 20, 30]);
+
+			let selected = Utxo::select_inputs(karl, 55).unwrap();
+			assert_eq!(selected.len(), 3);
+			assert_eq!(selected.iter().map(|(_, value)| value).sum::<Value>(), 60);
+		});
+	}
+
+	#[test]
+	fn test_select_inputs_insufficient_balance() {
+		new_test_ext().execute_with(|| {
+			let karl = H256::repeat_byte(7);
+			seed_utxos(karl, &[10, 20, 30]);
+
+			assert_eq!(Utxo::select_inputs(karl, 61), None);
+		});
+	}
+
+	#[test]
+	fn test_select_inputs_prefers_larger_utxos() {
+		new_test_ext().execute_with(|| {
+			let karl = H256::repeat_byte(7);
+			seed_utxos(karl, &[10, 20, 30]);
+
+			assert_eq!(Utxo::select_inputs(karl, 25), Some(vec![(H256::repeat_byte(3), 30)]));
+			assert_eq!(
+				Utxo::select_inputs(karl, 45),
+				Some(vec![(H256::repeat_byte(3), 30), (H256::repeat_byte(2), 20)])
+			);
+		});
+	}
+
+	#[test]
+	fn test_select_inputs_skips_unspendable_utxos() {
+		new_test_ext().execute_with(|| {
+			let karl = H256::repeat_byte(7);
+			seed_utxos(karl, &[10, 20, 30, 40]);
+			assert_ok!(Utxo::ensure_supply_invariant());
+			UtxoStore::mutate(H256::repeat_byte(2), |utxo| {
+				utxo.as_mut().unwrap().lock = Lock::MultiSig { threshold: 2, keys: vec![karl, H256::repeat_byte(8)] };
+			});
+			UtxoStore::mutate(H256::repeat_byte(3), |utxo| utxo.as_mut().unwrap().lock_until = Some(5));
+			RewardMaturity::insert(H256::repeat_byte(4), 0);
+
+			// only the plain output is usable, and another owner's UTXOs are never picked
+			assert_eq!(Utxo::select_inputs(karl, 10), Some(vec![(H256::repeat_byte(1), 10)]));
+			assert_eq!(Utxo::select_inputs(karl, 11), None);
+
+			system::Module::<Test>::set_block_number(5);
+			assert_eq!(
+				Utxo::select_inputs(karl, 80),
+				Some(vec![(H256::repeat_byte(4), 40), (H256::repeat_byte(3), 30), (H256::repeat_byte(1), 10)])
+			);
+		});
+	}
+	#[test]
+	fn test_treasury_share() {
+		new_test_ext().execute_with(|| {
+			let alice_pub_key = sp_io::crypto::sr25519_public_keys(SR25519)[0];
+
+			let mut transaction = paying(
+				vec![input(H256::from(GENESIS_UTXO))],
+				vec![output(50, H256::from(alice_pub_key))],
+			);
+
+			let alice_signature = sp_io::crypto::sr25519_sign(SR25519, &alice_pub_key, &Utxo::get_simple_transaction(&transaction)).unwrap();
+			transaction.inputs[0].sigscript = H512::from(alice_signature);
+			let treasury_utxo_hash = BlakeTwo256::hash_of(&(&transaction.encode(), 1 as u64));
+
+			// reward of 50: 10% to the treasury, the rest to the validators
+			assert_ok!(Utxo::spend(Origin::signed(0), transaction));
+			assert_eq!(UtxoStore::get(treasury_utxo_hash), Some(output(5, H256::from(TREASURY))));
+			assert_eq!(Utxo::reward_total(), 45);
+		});
+	}
+	#[test]
+	fn test_strict_output_ordering() {
+		new_test_ext_without_signatures().execute_with(|| {
+			STRICT_OUTPUT_ORDERING.with(|v| *v.borrow_mut() = true);
+			let output = |value, key| output(value, H256::repeat_byte(key));
+			let mut transaction = paying(
+				vec![input(H256::from(GENESIS_UTXO))],
+				vec![output(30, 1), output(10, 2), output(20, 2)],
+			);
+
+			assert!(Utxo::validate_transaction(&transaction).is_ok());
+
+			transaction.outputs.swap(0, 2);
+			assert_err!(Utxo::validate_transaction(&transaction), UtxoError::OutputsNotCanonical);
+		});
+	}
+	#[test]
+	fn test_spend_unchecked_requires_root() {
+		new_test_ext().execute_with(|| {
+			let alice_pub_key = sp_io::crypto::sr25519_public_keys(SR25519)[0];
+			let transaction = sign_transaction(paying(
+				vec![input(H256::from(GENESIS_UTXO))],
+				vec![output(150, H256::from(alice_pub_key))],
+			), &alice_pub_key);
+
+			assert_err!(
+				Utxo::spend(Origin::signed(0), transaction.clone()),
+				"output value must not exceed input value"
+			);
+			assert_err!(
+				Utxo::spend_unchecked(Origin::signed(0), transaction.clone()),
+				sp_runtime::traits::BadOrigin
+			);
+
+			// root may mint value out of thin air
+			assert_ok!(Utxo::spend_unchecked(Origin::ROOT, transaction.clone()));
+			let new_utxo_hash = BlakeTwo256::hash_of(&(&transaction.encode(), 0 as u64));
+			assert_eq!(UtxoStore::get(new_utxo_hash).map(|utxo| utxo.value), Some(150));
+			assert!(!UtxoStore::contains_key(H256::from(GENESIS_UTXO)));
+			assert_eq!(Utxo::reward_total(), 0);
+			assert_eq!(Utxo::total_supply(), 150);
+			assert_ok!(Utxo::ensure_supply_invariant());
+		});
+	}
+	#[test]
+	fn test_compact_dust() {
+		use frame_support::weights::GetDispatchInfo;
+
+		new_test_ext().execute_with(|| {
+			let karl = H256::repeat_byte(7);
+			seed_utxos(karl, &[1, 4, 5, 30]);
+
+			assert_err!(Utxo::compact_dust(Origin::signed(0), 5), sp_runtime::traits::BadOrigin);
+			let info = Call::<Test>::compact_dust(5).get_dispatch_info();
+			assert_eq!((info.weight, info.class), (500_000_000, DispatchClass::Operational));
+			assert_ok!(Utxo::compact_dust(Origin::ROOT, 5));
+
+			assert!(!UtxoStore::contains_key(H256::repeat_byte(1)));
+			assert!(!UtxoStore::contains_key(H256::repeat_byte(2)));
+			assert!(UtxoStore::contains_key(H256::repeat_byte(3)));
+			assert!(UtxoStore::contains_key(H256::repeat_byte(4)));
+			assert!(UtxoStore::contains_key(H256::from(GENESIS_UTXO)));
+			assert_eq!(Utxo::reward_total(), 5);
+			assert_eq!(
+				system::Module::<Test>::events().pop().map(|record| record.event),
+				Some(TestEvent::utxo(Event::DustCompacted(2, 5)))
+			);
+		});
+	}
+	#[test]
+	fn test_verify_input_signature() {
+		new_test_ext().execute_with(|| {
+			let alice_pub_key = sp_io::crypto::sr25519_public_keys(SR25519)[0];
+			let transaction = sign_transaction(paying(
+				vec![input(H256::from(GENESIS_UTXO))],
+				vec![output(50, H256::from(alice_pub_key))],
+			), &alice_pub_key);
+
+			assert!(Utxo::verify_input_signature(&transaction, 0, H256::from(alice_pub_key)));
+			assert!(!Utxo::verify_input_signature(&transaction, 0, H256::repeat_byte(7)));
+			assert!(!Utxo::verify_input_signature(&transaction, 1, H256::from(alice_pub_key)));
+		});
+	}
+	#[test]
+	fn test_batch_verify_matches_per_signature() {
+		new_test_ext().execute_with(|| {
+			let alice_pub_key = sp_io::crypto::sr25519_public_keys(SR25519)[0];
+			let alice = H256::from(alice_pub_key);
+			seed_utxos(alice, &[10, 20, 30]);
+
+			let transaction = sign_transaction(paying(
+				(1..=3).map(|byte| input(H256::repeat_byte(byte))).collect(),
+				vec![output(60, alice)],
+			), &alice_pub_key);
+			let mut tampered = transaction.clone();
+			tampered.inputs[1].sigscript = H512::repeat_byte(1);
+
+			for transaction in &[transaction, tampered] {
+				let simple_transaction = Utxo::get_simple_transaction(transaction);
+				let pairs: Vec<_> = transaction.inputs.iter().map(|input| (input.sigscript, alice)).collect();
+				let per_signature = (0..transaction.inputs.len())
+					.all(|index| Utxo::verify_input_signature(transaction, index, alice));
+
+				assert_eq!(Utxo::batch_verify(&simple_transaction, &pairs), per_signature);
+				assert_eq!(Utxo::validate_transaction(transaction).is_ok(), per_signature);
+			}
+		});
+	}
+	#[test]
+	fn test_spend_with_auto_change() {
+		new_test_ext().execute_with(|| {
+			let alice_pub_key = sp_io::crypto::sr25519_public_keys(SR25519)[0];
+			let recipient = H256::repeat_byte(1);
+			let change_to = H256::repeat_byte(2);
+			let inputs = vec![input(H256::from(GENESIS_UTXO))];
+
+			let transaction = sign_transaction(
+				Utxo::with_change(inputs, recipient, 30, change_to, 1, u64::MAX).unwrap(),
+				&alice_pub_key,
+			);
+			assert_ok!(Utxo::spend_with_auto_change(
+				Origin::signed(0), transaction.inputs.clone(), recipient, 30, change_to, 1, u64::MAX
+			));
+
+			let created = |index: u64| UtxoStore::get(BlakeTwo256::hash_of(&(&transaction.encode(), index)));
+			assert_eq!(created(0), Some(output(30, recipient)));
+			assert_eq!(created(1), Some(output(69, change_to)));
+			assert_eq!(
+				system::Module::<Test>::events().pop().map(|record| record.event),
+				Some(TestEvent::utxo(Event::TransactionSuccess(
+					transaction.clone(),
+					1,
+					vec![
+						BlakeTwo256::hash_of(&(&transaction.encode(), 0 as u64)),
+						BlakeTwo256::hash_of(&(&transaction.encode(), 1 as u64)),
+					]
+				)))
+			);
+		});
+	}
+	#[test]
+	fn test_double_spends_conflict_in_pool() {
+		new_test_ext_without_signatures().execute_with(|| {
+			let spending = |outpoint, value| paying(
+				vec![input(outpoint)],
+				vec![output(value, H256::repeat_byte(1))],
+			);
+			let conflicting = |a: &Transaction, b: &Transaction| {
+				let a = Utxo::validate_transaction(a).unwrap();
+				let b = Utxo::validate_transaction(b).unwrap();
+				a.provides.iter().any(|tag| b.provides.contains(tag))
+			};
+
+			let first = spending(H256::from(GENESIS_UTXO), 50);
+			let second = spending(H256::from(GENESIS_UTXO), 60);
+			assert!(conflicting(&first, &second));
+
+			// both spend the output `first` has yet to create
+			let pending = BlakeTwo256::hash_of(&(&first.encode(), 0 as u64));
+			let (third, fourth) = (spending(pending, 40), spending(pending, 45));
+			assert_eq!(Utxo::validate_transaction(&third).unwrap().requires, vec![pending.as_fixed_bytes().to_vec()]);
+			assert!(conflicting(&third, &fourth));
+
+			assert!(!conflicting(&first, &third));
+		});
+	}
+	#[test]
+	fn test_frozen_keys_cannot_spend_or_receive() {
+		new_test_ext().execute_with(|| {
+			let alice_pub_key = sp_io::crypto::sr25519_public_keys(SR25519)[0];
+			let bob = H256::repeat_byte(2);
+			let paying = |pubkey| sign_transaction(paying(
+				vec![input(H256::from(GENESIS_UTXO))],
+				vec![output(50, pubkey)],
+			), &alice_pub_key);
+
+			assert_err!(Utxo::set_frozen(Origin::signed(0), bob, true), sp_runtime::traits::BadOrigin);
+			assert_ok!(Utxo::set_frozen(Origin::ROOT, bob, true));
+			assert_err!(Utxo::spend(Origin::signed(0), paying(bob)), "recipient frozen");
+
+			assert_ok!(Utxo::set_frozen(Origin::ROOT, H256::from(alice_pub_key), true));
+			assert_err!(Utxo::spend(Origin::signed(0), paying(H256::repeat_byte(3))), "spender frozen");
+
+			assert_ok!(Utxo::set_frozen(Origin::ROOT, H256::from(alice_pub_key), false));
+			assert_ok!(Utxo::spend(Origin::signed(0), paying(H256::repeat_byte(3))));
+		});
+	}
+	#[test]
+	#[should_panic(expected = "genesis_utxos is empty")]
+	fn test_empty_genesis_is_rejected() {
+		let _ = GenesisConfig::default().build_storage();
+	}
+	#[test]
+	fn test_empty_genesis_can_be_allowed() {
+		let storage = GenesisConfig {
+			allow_empty_genesis: true,
+			..Default::default()
+		}.build_storage().unwrap();
+
+		sp_io::TestExternalities::from(storage).execute_with(|| {
+			assert_eq!(Utxo::utxo_count(), 0);
+		});
+	}
+	#[test]
+	fn test_expired_transaction_is_rejected() {
+		new_test_ext().execute_with(|| {
+			let alice_pub_key = sp_io::crypto::sr25519_public_keys(SR25519)[0];
+			let transaction = sign_transaction(Transaction {
+				valid_until: 3,
+				..paying(
+					vec![input(H256::from(GENESIS_UTXO))],
+					vec![output(50, H256::from(alice_pub_key))],
+				)
+			}, &alice_pub_key);
+
+			system::Module::<Test>::set_block_number(3);
+			assert_eq!(Utxo::validate_transaction(&transaction).map(|valid| valid.longevity), Ok(1));
+
+			system::Module::<Test>::set_block_number(4);
+			assert_err!(Utxo::spend(Origin::signed(0), transaction.clone()), "transaction expired");
+
+			// extending the deadline invalidates the signature
+			let extended = Transaction { valid_until: 10, ..transaction };
+			assert_err!(Utxo::spend(Origin::signed(0), extended), "signature must be valid");
+		});
+	}
+	#[test]
+	fn test_can_replace() {
+		new_test_ext().execute_with(|| {
+			let alice_pub_key = sp_io::crypto::sr25519_public_keys(SR25519)[0];
+			let paying = |value| paying(
+				vec![input(H256::from(GENESIS_UTXO))],
+				vec![output(value, H256::from(alice_pub_key))],
+			);
+			let old = paying(90);
+			let unrelated = Transaction {
+				inputs: vec![input(H256::repeat_byte(1))],
+				..paying(10)
+			};
+
+			assert!(Utxo::can_replace(&old, &paying(80)));
+			assert!(! Utxo::can_replace(&old, &paying(90)));
+			assert!(! Utxo::can_replace(&old, &paying(95)));
+			assert!(! Utxo::can_replace(&old, &unrelated));
+		});
+	}
+	#[test]
+	fn test_structural_check_needs_no_storage() {
+		// no externalities here: any `UtxoStore` access would panic
+		let spent = input(H256::from(GENESIS_UTXO));
+		let created = output(50, H256::repeat_byte(1));
+		let valid = paying(vec![spent.clone()], vec![created.clone()]);
+
+		assert_ok!(Utxo::structural_check(&valid));
+		assert_err!(
+			Utxo::structural_check(&Transaction { inputs: vec![], ..valid.clone() }),
+			UtxoError::NoInputs
+		);
+		assert_err!(
+			Utxo::structural_check(&Transaction { outputs: vec![], ..valid.clone() }),
+			UtxoError::NoOutputs
+		);
+		assert_err!(
+			Utxo::structural_check(&Transaction { inputs: vec![spent.clone(), spent], ..valid.clone() }),
+			UtxoError::DuplicateInput
+		);
+		assert_err!(
+			Utxo::structural_check(&Transaction { outputs: vec![created.clone(), created], ..valid.clone() }),
+			UtxoError::DuplicateOutput
+		);
+		assert_err!(
+			Utxo::structural_check(&Transaction { outputs: vec![output(0, H256::repeat_byte(1))], ..valid }),
+			UtxoError::OutputValueZero
+		);
+	}
+	#[test]
+	fn test_validation_errors_match_their_causes() {
+		new_test_ext().execute_with(|| {
+			let alice_pub_key = sp_io::crypto::sr25519_public_keys(SR25519)[0];
+			let alice = H256::from(alice_pub_key);
+			seed_utxos(alice, &[Value::max_value()]);
+			let paying = |outpoint, values: &[Value]| sign_transaction(Transaction {
+				valid_until: 1,
+				..paying(
+					vec![input(outpoint)],
+					values.iter()
+						.enumerate()
+						.map(|(index, value)| output(*value, H256::repeat_byte(index as u8 + 1)))
+						.collect(),
+				)
+			}, &alice_pub_key);
+			let genesis = H256::from(GENESIS_UTXO);
+
+			let mut unsigned = paying(genesis, &[50]);
+			unsigned.inputs[0].sigscript = H512::zero();
+			assert_eq!(Utxo::validate_transaction(&unsigned), Err(UtxoError::BadSignature));
+
+			assert_eq!(Utxo::validate_transaction(&paying(genesis, &[101])), Err(UtxoError::OutputExceedsInput));
+
+			let existing = paying(genesis, &[50]);
+			UtxoStore::insert(BlakeTwo256::hash_of(&(&existing.encode(), 0 as u64)), TransactionOutput::default());
+			assert_eq!(Utxo::validate_transaction(&existing), Err(UtxoError::OutputAlreadyExists));
+
+			let overflowing = Transaction {
+				inputs: vec![input(genesis), input(H256::repeat_byte(1))],
+				..paying(genesis, &[5])
+			};
+			assert_eq!(
+				Utxo::validate_transaction(&sign_transaction(overflowing, &alice_pub_key)),
+				Err(UtxoError::InputValueOverflow)
+			);
+			assert_eq!(
+				Utxo::validate_transaction(&paying(H256::repeat_byte(1), &[Value::max_value(), 5])),
+				Err(UtxoError::OutputValueOverflow)
+			);
+
+			assert_ok!(Utxo::set_frozen(Origin::ROOT, H256::repeat_byte(1), true));
+			assert_eq!(Utxo::validate_transaction(&paying(genesis, &[50])), Err(UtxoError::RecipientFrozen));
+			assert_ok!(Utxo::set_frozen(Origin::ROOT, alice, true));
+			assert_eq!(Utxo::validate_transaction(&paying(genesis, &[50])), Err(UtxoError::SpenderFrozen));
+			assert_ok!(Utxo::set_frozen(Origin::ROOT, alice, false));
+
+			system::Module::<Test>::set_block_number(2);
+			assert_eq!(Utxo::validate_transaction(&paying(genesis, &[50])), Err(UtxoError::TransactionExpired));
+
+			// dispatch surfaces the same errors
+			assert_err!(Utxo::spend(Origin::signed(0), paying(genesis, &[50])), UtxoError::TransactionExpired);
+		});
+	}
+	// Alice locks 90 of her genesis UTXO in an escrow paying Karl, refundable to her after block 10
+	fn setup_escrow() -> (H256, H256, H256) {
+		let alice_pub_key = sp_io::crypto::sr25519_public_keys(SR25519)[0];
+		let karl_pub_key = sp_io::crypto::sr25519_generate(SR25519, Some(KARL_PHRASE.as_bytes().to_vec()));
+
+		let mut transaction = paying(
+			vec![input(H256::from(GENESIS_UTXO))],
+			vec![TransactionOutput {
+				lock: Lock::Escrow { fallback: H256::from(alice_pub_key), deadline: 10 },
+				..output(90, H256::from(karl_pub_key))
+			}],
+		);
+		let alice_signature = sp_io::crypto::sr25519_sign(SR25519, &alice_pub_key, &Utxo::get_simple_transaction(&transaction)).unwrap();
+		transaction.inputs[0].sigscript = H512::from(alice_signature);
+		let escrow_hash = BlakeTwo256::hash_of(&(&transaction.encode(), 0 as u64));
+		assert_ok!(Utxo::spend(Origin::signed(0), transaction));
+
+		(escrow_hash, H256::from(alice_pub_key), H256::from(karl_pub_key))
+	}
+
+	fn spend_escrow(escrow_hash: H256, signer: H256) -> DispatchResult {
+		let signer = sp_core::sr25519::Public::from_h256(signer);
+		let mut transaction = paying(
+			vec![input(escrow_hash)],
+			vec![output(80, H256::from(signer))],
+		);
+		let signature = sp_io::crypto::sr25519_sign(SR25519, &signer, &Utxo::get_simple_transaction(&transaction)).unwrap();
+		transaction.inputs[0].sigscript = H512::from(signature);
+		Utxo::spend(Origin::signed(0), transaction)
+	}
+
+	#[test]
+	fn test_escrow_recipient_spends_before_deadline() {
+		new_test_ext().execute_with(|| {
+			let (escrow_hash, _, karl) = setup_escrow();
+			system::Module::<Test>::set_block_number(5);
+			assert_ok!(spend_escrow(escrow_hash, karl));
+		});
+	}
+
+	#[test]
+	fn test_escrow_sender_cannot_reclaim_before_deadline() {
+		new_test_ext().execute_with(|| {
+			let (escrow_hash, alice, _) = setup_escrow();
+			system::Module::<Test>::set_block_number(10);
+			assert_err!(spend_escrow(escrow_hash, alice), "escrow deadline not reached");
+			assert!(UtxoStore::contains_key(escrow_hash));
+		});
+	}
+
+	#[test]
+	fn test_escrow_sender_reclaims_after_deadline() {
+		new_test_ext().execute_with(|| {
+			let (escrow_hash, alice, _) = setup_escrow();
+			system::Module::<Test>::set_block_number(11);
+			assert_ok!(spend_escrow(escrow_hash, alice));
+			assert!(! UtxoStore::contains_key(escrow_hash));
+		});
+	}
+
+	#[test]
+	fn test_simple_transaction_computed_once() {
+		new_test_ext().execute_with(|| {
+			let alice_pub_key = sp_io::crypto::sr25519_public_keys(SR25519)[0];
+			seed_utxos(H256::from(alice_pub_key), &[1; 50]);
+
+			let mut transaction = paying(
+				(1..=50).map(|byte| input(H256::repeat_byte(byte))).collect(),
+				vec![output(40, H256::from(alice_pub_key))],
+			);
+			let simple_transaction = Utxo::get_simple_transaction(&transaction);
+			for input in transaction.inputs.iter_mut() {
+				let signature = sp_io::crypto::sr25519_sign(SR25519, &alice_pub_key, &simple_transaction).unwrap();
+				input.sigscript = H512::from(signature);
+			}
+
+			SIMPLE_TRANSACTION_CALLS.with(|calls| calls.set(0));
+			assert!(Utxo::validate_transaction(&transaction).is_ok());
+			assert_eq!(SIMPLE_TRANSACTION_CALLS.with(|calls| calls.get()), 1);
+
+			// every input verifies against the very same bytes
+			let simple_transaction = Utxo::get_simple_transaction(&transaction);
+			assert!(transaction.inputs.iter().all(|input| sp_io::crypto::sr25519_verify(
+				&Signature::from_raw(*input.sigscript.as_fixed_bytes()),
+				&simple_transaction,
+				&alice_pub_key,
+			)));
+		});
+	}
+	// signs every input of `transaction` with `key`
+	fn sign_transaction(mut transaction: Transaction, key: &sp_core::sr25519::Public) -> Transaction {
+		let simple_transaction = Utxo::get_simple_transaction(&transaction);
+		let signature = H512::from(sp_io::crypto::sr25519_sign(SR25519, key, &simple_transaction).unwrap());
+		for input in transaction.inputs.iter_mut() {
+			input.sigscript = signature;
+		}
+		transaction
+	}
+
+	// applies every spend of a block, then pays out the rewards as `on_finalize` would
+	fn run_block(transactions: Vec<Transaction>, authorities: &[H256]) {
+		for transaction in transactions {
+			assert_ok!(Utxo::spend(Origin::signed(0), transaction));
+		}
+		Utxo::disperse_rewards(authorities);
+	}
+
+	#[test]
+	fn test_run_block() {
+		new_test_ext().execute_with(|| {
+			let alice_pub_key = sp_io::crypto::sr25519_public_keys(SR25519)[0];
+			let validator = H256::repeat_byte(1);
+
+			let transaction = sign_transaction(paying(
+				vec![input(H256::from(GENESIS_UTXO))],
+				vec![output(50, H256::from(alice_pub_key))],
+			), &alice_pub_key);
+			let new_utxo_hash = BlakeTwo256::hash_of(&(&transaction.encode(), 0 as u64));
+
+			run_block(vec![transaction], &[validator]);
+
+			assert_eq!(UtxoStore::get(new_utxo_hash).map(|utxo| utxo.value), Some(50));
+			// reward of 50, less the 10% treasury share
+			let reward_utxo = output(45, validator);
+			let reward_utxo_hash = BlakeTwo256::hash_of(&(&reward_utxo, system::Module::<Test>::block_number()));
+			assert_eq!(UtxoStore::get(reward_utxo_hash), Some(reward_utxo));
+			assert_eq!(Utxo::reward_total(), 0);
+		});
+	}
+	#[test]
+	fn test_total_fees_collected() {
+		new_test_ext().execute_with(|| {
+			let alice_pub_key = sp_io::crypto::sr25519_public_keys(SR25519)[0];
+			let validator = H256::repeat_byte(1);
+			let paying = |outpoint, value| sign_transaction(paying(
+				vec![input(outpoint)],
+				vec![output(value, H256::from(alice_pub_key))],
+			), &alice_pub_key);
+
+			// 100 in, 90 out
+			let first = paying(H256::from(GENESIS_UTXO), 90);
+			let first_utxo_hash = BlakeTwo256::hash_of(&(&first.encode(), 0 as u64));
+			run_block(vec![first], &[validator]);
+
+			// 90 in, 85 out
+			system::Module::<Test>::set_block_number(2);
+			run_block(vec![paying(first_utxo_hash, 85)], &[validator]);
+
+			assert_eq!(Utxo::reward_total(), 0);
+			assert_eq!(Utxo::total_fees_collected(), 15);
+		});
+	}
+	#[test]
+	fn test_set_summary() {
+		new_test_ext().execute_with(|| {
+			let alice_pub_key = sp_io::crypto::sr25519_public_keys(SR25519)[0];
+			assert_eq!(Utxo::set_summary(), (1, 100, 0));
+
+			let transaction = sign_transaction(paying(
+				vec![input(H256::from(GENESIS_UTXO))],
+				vec![output(50, H256::from(alice_pub_key))],
+			), &alice_pub_key);
+			assert_ok!(Utxo::spend(Origin::signed(0), transaction));
+
+			// the new output and the 5 treasury UTXO, with the other 45 waiting in the reward pool
+			assert_eq!(Utxo::set_summary(), (2, 55, 45));
+		});
+	}
+	#[test]
+	fn test_treasury_dust_is_carried() {
+		new_test_ext().execute_with(|| {
+			let alice_pub_key = sp_io::crypto::sr25519_public_keys(SR25519)[0];
+			let validator = H256::repeat_byte(20);
+			seed_utxos(H256::from(alice_pub_key), &[10, 10, 10, 10]);
+			let treasury_total = || UtxoStore::iter()
+				.filter(|(_, utxo)| utxo.pubkey == H256::from(TREASURY))
+				.map(|(_, utxo)| utxo.value)
+				.sum::<Value>();
+
+			// each spend pays a fee of 5, so the 10% treasury cut is half a unit
+			for block in 1..=4u8 {
+				system::Module::<Test>::set_block_number(block as u64);
+				run_block(vec![sign_transaction(paying(
+					vec![input(H256::repeat_byte(block))],
+					vec![output(5, H256::from(alice_pub_key))],
+				), &alice_pub_key)], &[validator]);
+
+				assert_eq!(treasury_total(), block as Value / 2);
+				assert_eq!(Utxo::reward_dust(), (block as Value % 2) * REWARD_PRECISION / 2);
+			}
+			// nothing was rounded away: 20 in fees, 2 to the treasury, 18 to the validator
+			let validator_total: Value = UtxoStore::iter()
+				.filter(|(_, utxo)| utxo.pubkey == validator)
+				.map(|(_, utxo)| utxo.value)
+				.sum();
+			assert_eq!(validator_total, 18);
+		});
+	}
+	fn set_authorities(authorities: &[H256]) {
+		use frame_support::storage::StorageValue;
+		aura::Authorities::<crate::Runtime>::put(
+			authorities.iter().map(|key| sp_consensus_aura::sr25519::AuthorityId::from(Public::from_h256(*key))).collect::<Vec<_>>()
+		);
+	}
+
+	#[test]
+	fn test_on_finalize_pays_every_authority() {
+		new_test_ext().execute_with(|| {
+			use sp_runtime::traits::OnFinalize;
+			let authorities = [H256::repeat_byte(1), H256::repeat_byte(2)];
+			set_authorities(&authorities);
+			RewardTotal::put(10);
+
+			Utxo::on_finalize(1);
+
+			for authority in authorities.iter() {
+				let reward_utxo = output(5, *authority);
+				let reward_utxo_hash = BlakeTwo256::hash_of(&(&reward_utxo, 1 as u64));
+				assert_eq!(UtxoStore::get(reward_utxo_hash), Some(reward_utxo));
+			}
+			assert_eq!(Utxo::reward_total(), 0);
+		});
+	}
+
+	#[test]
+	fn test_on_finalize_without_authorities_keeps_reward() {
+		new_test_ext().execute_with(|| {
+			use sp_runtime::traits::OnFinalize;
+			set_authorities(&[]);
+			RewardTotal::put(10);
+
+			Utxo::on_finalize(1);
+
+			assert_eq!(Utxo::reward_total(), 10);
+		});
+	}
+	#[test]
+	fn test_tip_to() {
+		new_test_ext().execute_with(|| {
+			let alice_pub_key = sp_io::crypto::sr25519_public_keys(SR25519)[0];
+			let validator = H256::repeat_byte(1);
+			set_authorities(&[validator]);
+			let tipping = |outpoint, tip_to| sign_transaction(Transaction {
+				tip_to: Some(tip_to),
+				..paying(
+					vec![input(outpoint)],
+					vec![output(50, H256::from(alice_pub_key))],
+				)
+			}, &alice_pub_key);
+
+			// reward of 50, less the 10% treasury share
+			let tipped = tipping(H256::from(GENESIS_UTXO), validator);
+			assert_ok!(Utxo::spend(Origin::signed(0), tipped.clone()));
+			assert_eq!(UtxoStore::get(BlakeTwo256::hash_of(&(&tipped.encode(), 2 as u64))), Some(output(45, validator)));
+			assert_eq!(Utxo::reward_total(), 0);
+
+			// 50 in, 40 out, tipping a key that is no authority
+			let first_output = BlakeTwo256::hash_of(&(&tipped.encode(), 0 as u64));
+			let mut untipped = tipping(first_output, H256::repeat_byte(2));
+			untipped.outputs[0].value = 40;
+			let untipped = sign_transaction(untipped, &alice_pub_key);
+			assert_ok!(Utxo::spend(Origin::signed(0), untipped.clone()));
+			assert!(!UtxoStore::contains_key(BlakeTwo256::hash_of(&(&untipped.encode(), 2 as u64))));
+			assert_eq!(Utxo::reward_total(), 9);
+		});
+	}
+	#[test]
+	fn test_spend_reads_each_input_once() {
+		new_test_ext().execute_with(|| {
+			let alice_pub_key = sp_io::crypto::sr25519_public_keys(SR25519)[0];
+			seed_utxos(H256::from(alice_pub_key), &[10, 20, 30]);
+
+			let transaction = sign_transaction(paying(
+				(1..=3).map(|byte| input(H256::repeat_byte(byte))).collect(),
+				vec![output(55, H256::from(alice_pub_key))],
+			), &alice_pub_key);
+			let new_utxo_hash = BlakeTwo256::hash_of(&(&transaction.encode(), 0 as u64));
+
+			INPUT_READS.with(|reads| reads.borrow_mut().clear());
+			OWNED_OUTPOINTS_UPDATES.with(|updates| updates.set(0));
+			assert_ok!(Utxo::spend(Origin::signed(0), transaction));
+			assert_eq!(
+				INPUT_READS.with(|reads| reads.borrow().clone()),
+				vec![H256::repeat_byte(1), H256::repeat_byte(2), H256::repeat_byte(3)]
+			);
+			// all three inputs are Alice's, so her outpoint index is read once rather than per input
+			assert_eq!(OWNED_OUTPOINTS_UPDATES.with(|updates| updates.get()), 1);
+			assert_eq!(
+				Utxo::owned_outpoints(H256::from(alice_pub_key)),
+				vec![H256::from(GENESIS_UTXO), new_utxo_hash]
+			);
+
+			assert!((1..=3).all(|byte| ! UtxoStore::contains_key(H256::repeat_byte(byte))));
+			assert_eq!(UtxoStore::get(new_utxo_hash).map(|utxo| utxo.value), Some(55));
+		});
+	}
+	#[test]
+	fn test_success_event_carries_fee_and_created() {
+		new_test_ext().execute_with(|| {
+			let alice_pub_key = sp_io::crypto::sr25519_public_keys(SR25519)[0];
+
+			let transaction = sign_transaction(paying(
+				vec![input(H256::from(GENESIS_UTXO))],
+				vec![output(60, H256::from(alice_pub_key)), output(25, H256::repeat_byte(1))],
+			), &alice_pub_key);
+
+			assert_ok!(Utxo::spend(Origin::signed(0), transaction.clone()));
+			// 100 in, 85 out, and the treasury's 1 of the fee in a third UTXO
+			let created: Vec<H256> = (0..3)
+				.map(|index| BlakeTwo256::hash_of(&(&transaction.encode(), index as u64)))
+				.collect();
+			assert_eq!(
+				system::Module::<Test>::events().pop().map(|record| record.event),
+				Some(TestEvent::utxo(Event::TransactionSuccess(transaction, 15, created.clone())))
+			);
+			assert_eq!(
+				created.iter().map(|outpoint| UtxoStore::get(outpoint).map(|utxo| utxo.value)).collect::<Vec<_>>(),
+				vec![Some(60), Some(25), Some(1)]
+			);
+		});
+	}
+
+	#[test]
+	fn test_excessive_fee_is_rejected() {
+		new_test_ext().execute_with(|| {
+			let alice_pub_key = sp_io::crypto::sr25519_public_keys(SR25519)[0];
+			let paying = |value| sign_transaction(paying(
+				vec![input(H256::from(GENESIS_UTXO))],
+				vec![output(value, H256::from(alice_pub_key))],
+			), &alice_pub_key);
+
+			// 100 in: a fee of 91 is more than 10 times the 9 paid out
+			assert_eq!(Utxo::validate_transaction(&paying(9)), Err(UtxoError::ExcessiveFee));
+			assert_err!(Utxo::spend(Origin::signed(0), paying(9)), "fee exceeds the allowed multiple of the output value");
+			assert_ok!(Utxo::spend(Origin::signed(0), paying(10)));
+		});
+	}
+
+	#[test]
+	fn test_reward_dispersed_event() {
+		new_test_ext().execute_with(|| {
+			let alice_pub_key = sp_io::crypto::sr25519_public_keys(SR25519)[0];
+			let authorities = vec![H256::repeat_byte(1), H256::repeat_byte(2)];
+
+			let transaction = sign_transaction(paying(
+				vec![input(H256::from(GENESIS_UTXO))],
+				vec![output(80, H256::from(alice_pub_key))],
+			), &alice_pub_key);
+
+			// a fee of 20, less the 10% treasury share, split between two validators
+			run_block(vec![transaction], &authorities);
+			assert_eq!(
+				system::Module::<Test>::events().pop().map(|record| record.event),
+				Some(TestEvent::utxo(Event::RewardDispersed(1, 9, authorities)))
+			);
+		});
+	}
+
+	#[test]
+	fn test_mint() {
+		new_test_ext().execute_with(|| {
+			let alice_pub_key = sp_io::crypto::sr25519_public_keys(SR25519)[0];
+			let minting = |value| output(value, H256::from(alice_pub_key));
+
+			assert_err!(Utxo::mint(Origin::signed(0), vec![minting(10)]), sp_runtime::traits::BadOrigin);
+			assert_err!(Utxo::mint(Origin::ROOT, vec![minting(10), minting(0)]), "Output value must be nonzero");
+			assert_eq!(Utxo::mint_nonce(), 0);
+
+			// the same output minted twice still lands at two outpoints
+			assert_ok!(Utxo::mint(Origin::ROOT, vec![minting(10), minting(10)]));
+			let minted = vec![
+				BlakeTwo256::hash_of(&(&minting(10), 0 as u64)),
+				BlakeTwo256::hash_of(&(&minting(10), 1 as u64)),
+			];
+			assert_eq!(
+				system::Module::<Test>::events().pop().map(|record| record.event),
+				Some(TestEvent::utxo(Event::Minted(minted.clone())))
+			);
+			assert_eq!(Utxo::mint_nonce(), 2);
+
+			let transaction = sign_transaction(paying(
+				minted.iter().map(|outpoint| input(*outpoint)).collect(),
+				vec![output(20, H256::repeat_byte(1))],
+			), &alice_pub_key);
+			assert_ok!(Utxo::spend(Origin::signed(0), transaction));
+			assert!(minted.iter().all(|outpoint| !UtxoStore::contains_key(outpoint)));
+		});
+	}
+
+	#[test]
+	fn test_build_sweep() {
+		new_test_ext().execute_with(|| {
+			let alice_pub_key = sp_io::crypto::sr25519_public_keys(SR25519)[0];
+			let alice = H256::from(alice_pub_key);
+			let safe_key = H256::repeat_byte(7);
+			seed_utxos(alice, &[10, 20, 30]);
+
+			let utxos: Vec<_> = (1..=3).map(|byte| (input(H256::repeat_byte(byte)), byte as Value * 10)).collect();
+
+			// only the compromised key's UTXOs can be swept, and the fee must be covered
+			assert_eq!(Utxo::build_sweep(safe_key, safe_key, utxos.clone(), 2), Err(UtxoError::NotOwner));
+			assert_eq!(Utxo::build_sweep(alice, safe_key, utxos.clone(), 61), Err(UtxoError::OutputExceedsInput));
+
+			let sweep = Utxo::build_sweep(alice, safe_key, utxos, 2).unwrap();
+			assert_eq!(sweep.inputs.len(), 3);
+			assert_eq!(sweep.outputs, vec![output(58, safe_key)]);
+
+			let sweep = sign_transaction(sweep, &alice_pub_key);
+			assert_eq!(Utxo::validate_transaction(&sweep).map(|valid| valid.priority), Ok(2));
+			assert_ok!(Utxo::spend(Origin::signed(0), sweep));
+		});
+	}
+
+	#[test]
+	fn test_burn() {
+		use frame_support::weights::GetDispatchInfo;
+
+		new_test_ext().execute_with(|| {
+			let alice_pub_key = sp_io::crypto::sr25519_public_keys(SR25519)[0];
+			let genesis = H256::from(GENESIS_UTXO);
+			let inputs = vec![input(genesis)];
+
+			assert_err!(Utxo::burn(Origin::signed(0), inputs.clone()), "signature must be valid");
+			assert!(UtxoStore::contains_key(genesis));
+
+			// capped and weighted like a spend, per input
+			let too_many: Vec<_> = (0..65).map(|i| input(H256::from_low_u64_be(i))).collect();
+			assert_err!(Utxo::burn(Origin::signed(0), too_many.clone()), "too many inputs");
+			assert_eq!(Utxo::validate_burn(&too_many).err(), Some(UtxoError::TooManyInputs));
+			assert_eq!(Call::<Test>::burn(too_many).get_dispatch_info().weight, SpendWeight::weight_of(65, 0));
+
+			assert!(Utxo::validate_burn(&inputs).is_err());
+			let signed = sign_transaction(Utxo::burning(inputs), &alice_pub_key).inputs;
+			assert_eq!(Utxo::validate_burn(&signed).map(|valid| valid.provides), Ok(vec![Utxo::spend_tag(&genesis)]));
+			assert_ok!(Utxo::burn(Origin::signed(0), signed));
+			assert!(!UtxoStore::contains_key(genesis));
+			assert_eq!(Utxo::reward_total(), 0);
+			assert_eq!(
+				system::Module::<Test>::events().pop().map(|record| record.event),
+				Some(TestEvent::utxo(Event::Burned(vec![genesis], 100)))
+			);
+		});
+	}
+
+	#[test]
+	fn test_minimum_fee() {
+		new_test_ext().execute_with(|| {
+			MINIMUM_FEE.with(|v| *v.borrow_mut() = 5);
+			let alice_pub_key = sp_io::crypto::sr25519_public_keys(SR25519)[0];
+			let paying = |value| sign_transaction(paying(
+				vec![input(H256::from(GENESIS_UTXO))],
+				vec![output(value, H256::from(alice_pub_key))],
+			), &alice_pub_key);
+
+			// 100 in
+			assert_eq!(Utxo::validate_transaction(&paying(96)), Err(UtxoError::FeeTooLow));
+			assert_eq!(Utxo::validate_transaction(&paying(95)).map(|valid| valid.priority), Ok(5));
+			assert_eq!(Utxo::validate_transaction(&paying(90)).map(|valid| valid.priority), Ok(10));
+
+			assert_err!(Utxo::spend(Origin::signed(0), paying(96)), "fee below the minimum");
+			assert_ok!(Utxo::spend(Origin::signed(0), paying(95)));
+			assert_eq!(Utxo::total_fees_collected(), 5);
+		});
+	}
+
+	#[test]
+	fn test_dependent_transactions_apply_parent_first() {
+		let alice_pub_key = || sp_io::crypto::sr25519_public_keys(SR25519)[0];
+		let paying = |outpoint, value| sign_transaction(paying(
+			vec![input(outpoint)],
+			vec![output(value, H256::from(alice_pub_key()))],
+		), &alice_pub_key());
+		let parent_and_child = || {
+			let parent = paying(H256::from(GENESIS_UTXO), 90);
+			let child = paying(BlakeTwo256::hash_of(&(&parent.encode(), 0 as u64)), 80);
+			(parent, child)
+		};
+
+		// the pool orders them through `requires`/`provides`; a block must apply them in that order
+		new_test_ext().execute_with(|| {
+			let (parent, child) = parent_and_child();
+			assert_err!(Utxo::spend(Origin::signed(0), child.clone()), "utxo does not exist");
+			assert_eq!(Utxo::total_fees_collected(), 0);
+			assert_ok!(Utxo::spend(Origin::signed(0), parent));
+			assert_ok!(Utxo::spend(Origin::signed(0), child));
+		});
+		new_test_ext().execute_with(|| {
+			let (parent, child) = parent_and_child();
+			assert_ok!(Utxo::spend(Origin::signed(0), parent));
+			assert_ok!(Utxo::spend(Origin::signed(0), child.clone()));
+			let child_utxo = BlakeTwo256::hash_of(&(&child.encode(), 0 as u64));
+			assert_eq!(UtxoStore::get(child_utxo).map(|utxo| utxo.value), Some(80));
+			assert_eq!(Utxo::total_fees_collected(), 20);
+		});
+	}
+
+	#[test]
+	fn test_spend_ed25519_input() {
+		new_test_ext().execute_with(|| {
+			let karl_pub_key = sp_io::crypto::ed25519_generate(ED25519, Some(KARL_PHRASE.as_bytes().to_vec()));
+			let karl = H256::from(karl_pub_key.0);
+			seed_utxos(karl, &[50]);
+
+			let mut transaction = paying(
+				vec![input(H256::repeat_byte(1))],
+				vec![output(40, H256::repeat_byte(2))],
+			);
+			assert_eq!(Utxo::validate_transaction(&transaction), Err(UtxoError::BadSignature));
+
+			let simple_transaction = Utxo::get_simple_transaction(&transaction);
+			let signature = sp_io::crypto::ed25519_sign(ED25519, &karl_pub_key, &simple_transaction).unwrap();
+			transaction.inputs[0].sigscript = H512::from(signature.0);
+			assert!(Utxo::verify_input_signature(&transaction, 0, karl));
+			assert_ok!(Utxo::spend(Origin::signed(0), transaction));
+			assert!(!UtxoStore::contains_key(H256::repeat_byte(1)));
+		});
+	}
+
+	#[test]
+	fn test_time_locked_output() {
+		new_test_ext().execute_with(|| {
+			let alice_pub_key = sp_io::crypto::sr25519_public_keys(SR25519)[0];
+			let locked = H256::repeat_byte(1);
+			UtxoStore::insert(locked, TransactionOutput {
+				lock_until: Some(5),
+				..output(50, H256::from(alice_pub_key))
+			});
+			let transaction = sign_transaction(paying(
+				vec![input(locked)],
+				vec![output(40, H256::repeat_byte(2))],
+			), &alice_pub_key);
+
+			system::Module::<Test>::set_block_number(4);
+			assert_eq!(Utxo::validate_transaction(&transaction), Err(UtxoError::OutputLocked));
+			assert_err!(Utxo::spend(Origin::signed(0), transaction.clone()), "utxo is time-locked");
+
+			system::Module::<Test>::set_block_number(5);
+			assert_ok!(Utxo::spend(Origin::signed(0), transaction));
+			assert!(!UtxoStore::contains_key(locked));
+		});
+	}
+
+	#[test]
+	fn test_runtime_upgrade_migrates_old_outputs() {
+		new_test_ext().execute_with(|| {
+			use sp_runtime::traits::OnRuntimeUpgrade;
+			assert_eq!(Utxo::storage_version(), STORAGE_VERSION);
+
+			// an output in the original layout, and one from after `lock` but before `lock_until`
+			let old = H256::repeat_byte(1);
+			frame_support::storage::unhashed::put(
+				&UtxoStore::hashed_key_for(old),
+				&(50 as Value, H256::repeat_byte(2)),
+			);
+			let multisig = Lock::MultiSig { threshold: 1, keys: vec![H256::repeat_byte(2)] };
+			let locked = H256::repeat_byte(3);
+			frame_support::storage::unhashed::put(
+				&UtxoStore::hashed_key_for(locked),
+				&(30 as Value, H256::repeat_byte(2), multisig.clone()),
+			);
+			StorageVersion::put(0);
+
+			Utxo::on_runtime_upgrade();
+			assert_eq!(Utxo::storage_version(), STORAGE_VERSION);
+			assert_eq!(UtxoStore::get(old), Some(output(50, H256::repeat_byte(2))));
+			assert_eq!(UtxoStore::get(locked), Some(TransactionOutput { lock: multisig, ..output(30, H256::repeat_byte(2)) }));
+			assert_eq!(UtxoStore::get(H256::from(GENESIS_UTXO)).map(|utxo| utxo.value), Some(100));
+			assert_eq!(Utxo::owned_outpoints(H256::repeat_byte(2)), vec![old, locked]);
+
+			// and one from before `relative_lock`
+			frame_support::storage::unhashed::put(
+				&UtxoStore::hashed_key_for(old),
+				&(50 as Value, H256::repeat_byte(2), Lock::Pubkey, Some(7u64)),
+			);
+			StorageVersion::put(3);
+
+			Utxo::on_runtime_upgrade();
+			assert_eq!(UtxoStore::get(old).map(|utxo| (utxo.lock_until, utxo.relative_lock)), Some((Some(7), 0)));
+		});
+	}
+
+	#[test]
+	fn test_reward_sink() {
+		let sink = H256::repeat_byte(7);
+		let storage = GenesisConfig {
+			reward_sink: Some(sink),
+			allow_empty_genesis: true,
+			..Default::default()
+		}.build_storage().unwrap();
+
+		sp_io::TestExternalities::from(storage).execute_with(|| {
+			use sp_runtime::traits::OnFinalize;
+			set_authorities(&[H256::repeat_byte(1), H256::repeat_byte(2)]);
+			RewardTotal::put(10);
+			system::Module::<Test>::set_block_number(1);
+
+			Utxo::on_finalize(1);
+
+			let reward_utxo = output(10, sink);
+			assert_eq!(UtxoStore::get(BlakeTwo256::hash_of(&(&reward_utxo, 1 as u64))), Some(reward_utxo));
+			assert_eq!(Utxo::reward_total(), 0);
+
+			assert_err!(Utxo::set_reward_sink(Origin::signed(0), None), sp_runtime::traits::BadOrigin);
+			assert_ok!(Utxo::set_reward_sink(Origin::ROOT, None));
+			assert_eq!(Utxo::reward_sink(), None);
+		});
+	}
+
+	#[test]
+	fn test_reward_maturity() {
+		new_test_ext().execute_with(|| {
+			let validator_pub_key = sp_io::crypto::sr25519_generate(SR25519, Some(KARL_PHRASE.as_bytes().to_vec()));
+			let validator = H256::from(validator_pub_key);
+			RewardTotal::put(10);
+			Utxo::disperse_rewards(&[validator]);
+
+			let reward_utxo = output(10, validator);
+			let reward_utxo_hash = BlakeTwo256::hash_of(&(&reward_utxo, 1 as u64));
+			assert_eq!(Utxo::reward_created_at(reward_utxo_hash), Some(1));
+			let transaction = sign_transaction(paying(
+				vec![input(reward_utxo_hash)],
+				vec![output(9, validator)],
+			), &validator_pub_key);
+
+			// created at block 1, so spendable from block 4
+			system::Module::<Test>::set_block_number(3);
+			assert_eq!(Utxo::validate_transaction(&transaction), Err(UtxoError::RewardImmature));
+			assert_err!(Utxo::spend(Origin::signed(0), transaction.clone()), "reward utxo is not mature yet");
+
+			system::Module::<Test>::set_block_number(4);
+			assert_ok!(Utxo::spend(Origin::signed(0), transaction));
+			assert_eq!(Utxo::reward_created_at(reward_utxo_hash), None);
+		});
+	}
+
+	#[test]
+	fn test_dust_threshold() {
+		new_test_ext().execute_with(|| {
+			let alice_pub_key = sp_io::crypto::sr25519_public_keys(SR25519)[0];
+			let paying = |value| sign_transaction(paying(
+				vec![input(H256::from(GENESIS_UTXO))],
+				vec![output(90, H256::from(alice_pub_key)), output(value, H256::repeat_byte(1))],
+			), &alice_pub_key);
+
+			assert_eq!(Utxo::validate_transaction(&paying(4)), Err(UtxoError::OutputBelowDustThreshold));
+			assert_err!(Utxo::spend(Origin::signed(0), paying(4)), "output value below the dust threshold");
+			// the fee of 5 left over is not an output, so it may be any amount
+			assert_ok!(Utxo::spend(Origin::signed(0), paying(5)));
+		});
+
+		// genesis outputs are not validated
+		let storage = GenesisConfig {
+			genesis_utxos: vec![output(1, H256::repeat_byte(1))],
+			..Default::default()
+		}.build_storage().unwrap();
+		sp_io::TestExternalities::from(storage).execute_with(|| {
+			assert_eq!(Utxo::set_summary(), (1, 1, 0));
+		});
+	}
+
+	#[test]
+	fn test_min_relay_fee_per_byte() {
+		new_test_ext().execute_with(|| {
+			MINIMUM_FEE.with(|v| *v.borrow_mut() = 5);
+			MIN_RELAY_FEE_PER_BYTE.with(|v| *v.borrow_mut() = 1);
+			let alice_pub_key = sp_io::crypto::sr25519_public_keys(SR25519)[0];
+			seed_utxos(H256::from(alice_pub_key), &[200; 10]);
+			let paying = |fee| sign_transaction(paying(
+				(1..=10).map(|byte| input(H256::repeat_byte(byte))).collect(),
+				vec![output(2000 - fee, H256::from(alice_pub_key))],
+			), &alice_pub_key);
+
+			// well above the flat minimum, but ten inputs take far more than 10 bytes
+			let size = paying(10).encode().len() as Value;
+			assert!(size > 10);
+			assert_eq!(Utxo::validate_transaction(&paying(10)), Err(UtxoError::FeeBelowRelayMinimum));
+			assert_err!(Utxo::spend(Origin::signed(0), paying(10)), "fee below the relay minimum for its size");
+			assert_eq!(Utxo::validate_transaction(&paying(size - 1)), Err(UtxoError::FeeBelowRelayMinimum));
+			assert_ok!(Utxo::spend(Origin::signed(0), paying(size)));
+		});
+	}
+
+	#[test]
+	fn test_owned_outpoints_index() {
+		new_test_ext().execute_with(|| {
+			let alice_pub_key = sp_io::crypto::sr25519_public_keys(SR25519)[0];
+			let karl_pub_key = sp_io::crypto::sr25519_generate(SR25519, Some(KARL_PHRASE.as_bytes().to_vec()));
+			let (alice, karl, bob) = (H256::from(alice_pub_key), H256::from(karl_pub_key), H256::repeat_byte(2));
+			let output = |value, pubkey| output(value, pubkey);
+			let spending = |outpoint, outputs, key| sign_transaction(paying(
+				vec![input(outpoint)],
+				outputs,
+			), key);
+			let outpoint = |transaction: &Transaction, index: u64| BlakeTwo256::hash_of(&(&transaction.encode(), index));
+			let outpoints_of = |pubkey| Utxo::utxos_of(pubkey).into_iter().map(|(outpoint, _)| outpoint).collect::<Vec<_>>();
+
+			assert_eq!(Utxo::utxos_of(alice), vec![(H256::from(GENESIS_UTXO), output(100, alice))]);
+
+			let first = spending(H256::from(GENESIS_UTXO), vec![output(40, karl), output(50, alice)], &alice_pub_key);
+			assert_ok!(Utxo::spend(Origin::signed(0), first.clone()));
+			assert_eq!(outpoints_of(alice), vec![outpoint(&first, 1)]);
+			assert_eq!(outpoints_of(karl), vec![outpoint(&first, 0)]);
+
+			let second = spending(outpoint(&first, 0), vec![output(30, bob)], &karl_pub_key);
+			assert_ok!(Utxo::spend(Origin::signed(0), second.clone()));
+			let third = spending(outpoint(&first, 1), vec![output(45, karl)], &alice_pub_key);
+			assert_ok!(Utxo::spend(Origin::signed(0), third.clone()));
+
+			assert_eq!(outpoints_of(alice), vec![]);
+			assert_eq!(Utxo::owned_outpoints(alice), vec![]);
+			assert_eq!(outpoints_of(karl), vec![outpoint(&third, 0)]);
+			assert_eq!(outpoints_of(bob), vec![outpoint(&second, 0)]);
+			// the treasury's 10% of each fee
+			assert_eq!(outpoints_of(H256::from(TREASURY)), vec![outpoint(&first, 2), outpoint(&second, 1)]);
+		});
+	}
+
+	#[test]
+	fn test_supply_invariant() {
+		new_test_ext().execute_with(|| {
+			let alice_pub_key = sp_io::crypto::sr25519_public_keys(SR25519)[0];
+			let alice = H256::from(alice_pub_key);
+			let authorities = [H256::repeat_byte(1), H256::repeat_byte(2)];
+			let paying = |outpoint, value| sign_transaction(paying(
+				vec![input(outpoint)],
+				vec![output(value, alice)],
+			), &alice_pub_key);
+			assert_eq!(Utxo::total_supply(), 100);
+			assert_ok!(Utxo::ensure_supply_invariant());
+
+			// each block spends the previous block's output, paying a fee that is split into the
+			// treasury cut, reward UTXOs and a remainder left in the pool
+			let mut outpoint = H256::from(GENESIS_UTXO);
+			for (block, value) in [(1, 93), (2, 81), (3, 80)].iter() {
+				system::Module::<Test>::set_block_number(*block);
+				let transaction = paying(outpoint, *value);
+				outpoint = BlakeTwo256::hash_of(&(&transaction.encode(), 0 as u64));
+				run_block(vec![transaction], &authorities);
+				assert_eq!(Utxo::total_supply(), 100);
+				assert_ok!(Utxo::ensure_supply_invariant());
+			}
+
+			assert_ok!(Utxo::mint(Origin::ROOT, vec![output(20, alice)]));
+			assert_eq!(Utxo::total_supply(), 120);
+			assert_ok!(Utxo::ensure_supply_invariant());
+
+			let burnt = sign_transaction(Utxo::burning(vec![input(outpoint)]), &alice_pub_key).inputs;
+			assert_ok!(Utxo::burn(Origin::signed(0), burnt));
+			assert_eq!(Utxo::total_supply(), 40);
+			assert_ok!(Utxo::ensure_supply_invariant());
+
+			// value created behind the supply's back is caught
+			UtxoStore::insert(H256::repeat_byte(9), TransactionOutput { value: 1, ..Default::default() });
+			assert_eq!(Utxo::ensure_supply_invariant(), Err(UtxoError::SupplyMismatch));
+		});
+	}
+
+	#[test]
+	fn test_trace_inputs() {
+		new_test_ext().execute_with(|| {
+			let alice_pub_key = sp_io::crypto::sr25519_public_keys(SR25519)[0];
+			let genesis_utxo = output(100, H256::from(alice_pub_key));
+			let transaction = sign_transaction(paying(
+				vec![input(H256::from(GENESIS_UTXO))],
+				vec![output(90, H256::repeat_byte(1))],
+			), &alice_pub_key);
+
+			assert_eq!(Utxo::trace_inputs(&transaction), vec![Some(genesis_utxo)]);
+			assert_ok!(Utxo::spend(Origin::signed(0), transaction.clone()));
+			assert_eq!(Utxo::trace_inputs(&transaction), vec![None]);
+		});
+	}
+
+	#[test]
+	fn test_spend_weight_scales_with_size() {
+		use frame_support::weights::GetDispatchInfo;
+
+		let transaction = |inputs: u64, outputs: u64| paying(
+			(0..inputs).map(|i| input(H256::from_low_u64_be(i))).collect(),
+			(0..outputs).map(|o| output(10, H256::from_low_u64_be(o))).collect(),
+		);
+		let small = Call::<Test>::spend(transaction(1, 1)).get_dispatch_info();
+		let wide = Call::<Test>::spend(transaction(100, 1)).get_dispatch_info();
+		let large = Call::<Test>::spend(transaction(100, 100)).get_dispatch_info();
+
+		assert_eq!(small.weight, SpendWeight::BASE + SpendWeight::PER_INPUT + SpendWeight::PER_OUTPUT);
+		assert!(wide.weight > small.weight);
+		assert!(large.weight > wide.weight);
+		assert_eq!(large.class, DispatchClass::Normal);
+		assert!(large.pays_fee);
+
+		// the payment and the change, whatever the amounts
+		let inputs: Vec<_> = (0..3).map(|i| input(H256::from_low_u64_be(i))).collect();
+		let with_change = Call::<Test>::spend_with_auto_change(inputs, H256::zero(), 1, H256::zero(), 1, 0);
+		assert_eq!(with_change.get_dispatch_info().weight, SpendWeight::weight_of(3, 2));
+	}
+
+	#[test]
+	fn test_spend_authority() {
+		new_test_ext().execute_with(|| {
+			let alice_pub_key = sp_io::crypto::sr25519_public_keys(SR25519)[0];
+			let alice = H256::from(alice_pub_key);
+			let karl_pub_key = sp_io::crypto::sr25519_generate(SR25519, Some(KARL_PHRASE.as_bytes().to_vec()));
+			let karl = H256::from(karl_pub_key);
+			let transaction = sign_transaction(paying(
+				vec![input(H256::from(GENESIS_UTXO))],
+				vec![output(90, karl)],
+			), &karl_pub_key);
+
+			// karl is not authorized yet, and only alice can authorize a key
+			assert_err!(Utxo::spend(Origin::signed(0), transaction.clone()), UtxoError::BadSignature);
+			let payload = Utxo::spend_authority_payload(alice, &[karl], 0);
+			let forged = H512::from(sp_io::crypto::sr25519_sign(SR25519, &karl_pub_key, &payload).unwrap());
+			assert_err!(
+				Utxo::set_spend_authority(Origin::signed(0), alice, vec![karl], forged),
+				UtxoError::BadSignature
+			);
+
+			// signed for a chain with another genesis block
+			let genesis_hash = system::Module::<Test>::block_hash(0);
+			assert!(payload.starts_with(genesis_hash.as_bytes()));
+			let other_chain = (H256::repeat_byte(1), b"spend_authority", alice, &[karl][..], 0u64).encode();
+			let replayed = H512::from(sp_io::crypto::sr25519_sign(SR25519, &alice_pub_key, &other_chain).unwrap());
+			assert_err!(
+				Utxo::set_spend_authority(Origin::signed(0), alice, vec![karl], replayed),
+				UtxoError::BadSignature
+			);
+
+			let signature = H512::from(sp_io::crypto::sr25519_sign(SR25519, &alice_pub_key, &payload).unwrap());
+			assert_ok!(Utxo::set_spend_authority(Origin::signed(0), alice, vec![karl], signature));
+			assert_eq!(Utxo::spend_authority(alice), vec![karl]);
+			assert_eq!(Utxo::spend_authority_nonce(alice), 1);
+			assert_ok!(Utxo::can_spend(H256::from(GENESIS_UTXO), karl, 0));
+
+			// the consumed nonce can't be replayed
+			assert_err!(
+				Utxo::set_spend_authority(Origin::signed(0), alice, vec![karl], signature),
+				UtxoError::BadSignature
+			);
+
+			assert_ok!(Utxo::spend(Origin::signed(0), transaction));
+			assert!(!UtxoStore::contains_key(H256::from(GENESIS_UTXO)));
+		});
+	}
+
+	#[test]
+	fn test_multisig_output() {
+		new_test_ext().execute_with(|| {
+			let alice_pub_key = sp_io::crypto::sr25519_public_keys(SR25519)[0];
+			let karl_pub_key = sp_io::crypto::sr25519_generate(SR25519, Some(KARL_PHRASE.as_bytes().to_vec()));
+			let third_pub_key = sp_io::crypto::sr25519_generate(SR25519, None);
+			let keys = vec![H256::from(alice_pub_key), H256::from(karl_pub_key), H256::from(third_pub_key)];
+			let locked = H256::repeat_byte(1);
+			UtxoStore::insert(locked, TransactionOutput {
+				lock: Lock::MultiSig { threshold: 2, keys: keys.clone() },
+				..output(50, H256::from(alice_pub_key))
+			});
+
+			let mut transaction = paying(
+				vec![input(locked)],
+				vec![TransactionOutput {
+					lock: Lock::MultiSig { threshold: 4, keys: keys.clone() },
+					..output(40, H256::repeat_byte(2))
+				}],
+			);
+			assert_eq!(Utxo::validate_transaction(&transaction), Err(UtxoError::InvalidMultiSig));
+			transaction.outputs[0].lock = Lock::Pubkey;
+
+			let simple_transaction = Utxo::get_simple_transaction(&transaction);
+			let sign = |key| H512::from(sp_io::crypto::sr25519_sign(SR25519, key, &simple_transaction).unwrap());
+
+			// the owner alone, even signing twice, is one of the two keys needed
+			transaction.inputs[0].sigscript = sign(&alice_pub_key);
+			transaction.inputs[0].witness = vec![sign(&alice_pub_key)];
+			assert_err!(Utxo::spend(Origin::signed(0), transaction.clone()), UtxoError::MultiSigThresholdNotMet);
+			assert_err!(Utxo::can_spend(locked, H256::from(alice_pub_key), 0), UtxoError::NotOwner);
+
+			transaction.inputs[0].witness = vec![sign(&karl_pub_key)];
+			assert_ok!(Utxo::spend(Origin::signed(0), transaction));
+			assert!(!UtxoStore::contains_key(locked));
+		});
+	}
+
+	#[test]
+	fn test_htlc_output() {
+		new_test_ext().execute_with(|| {
+			let alice_pub_key = sp_io::crypto::sr25519_public_keys(SR25519)[0];
+			let karl_pub_key = sp_io::crypto::sr25519_generate(SR25519, Some(KARL_PHRASE.as_bytes().to_vec()));
+			let preimage = H512::repeat_byte(7);
+			let htlc = |outpoint| UtxoStore::insert(outpoint, TransactionOutput {
+				lock: Lock::Htlc {
+					hashlock: BlakeTwo256::hash(preimage.as_bytes()),
+					timeout: 5,
+					claim_key: H256::from(alice_pub_key),
+					refund_key: H256::from(karl_pub_key),
+				},
+				..output(50, H256::from(karl_pub_key))
+			});
+			htlc(H256::repeat_byte(1));
+			htlc(H256::repeat_byte(2));
+			// the witness isn't signed, so it can carry the preimage
+			let spending = |outpoint, witness, key| {
+				let transaction = paying(
+					vec![TransactionInput { witness, ..input(outpoint) }],
+					vec![output(40, H256::repeat_byte(3))],
+				);
+				sign_transaction(transaction, key)
+			};
+
+			// claim: alice reveals the preimage before the timeout
+			let wrong_preimage = spending(H256::repeat_byte(1), vec![H512::repeat_byte(8)], &alice_pub_key);
+			assert_err!(Utxo::spend(Origin::signed(0), wrong_preimage), UtxoError::HtlcBadPreimage);
+			assert_ok!(Utxo::spend(Origin::signed(0), spending(H256::repeat_byte(1), vec![preimage], &alice_pub_key)));
+
+			// refund: karl waits for the timeout to pass
+			let refund = spending(H256::repeat_byte(2), Vec::new(), &karl_pub_key);
+			system::Module::<Test>::set_block_number(5);
+			assert_err!(Utxo::spend(Origin::signed(0), refund.clone()), UtxoError::HtlcTimeoutNotReached);
+			system::Module::<Test>::set_block_number(6);
+			assert_err!(
+				Utxo::spend(Origin::signed(0), spending(H256::repeat_byte(2), vec![preimage], &alice_pub_key)),
+				UtxoError::HtlcExpired
+			);
+			assert_ok!(Utxo::spend(Origin::signed(0), refund));
+			assert!(!UtxoStore::contains_key(H256::repeat_byte(2)));
+		});
+	}
+
+	#[test]
+	fn test_relative_lock() {
+		new_test_ext().execute_with(|| {
+			let alice_pub_key = sp_io::crypto::sr25519_public_keys(SR25519)[0];
+			system::Module::<Test>::set_block_number(2);
+			let transaction = sign_transaction(paying(
+				vec![input(H256::from(GENESIS_UTXO))],
+				vec![TransactionOutput { relative_lock: 5, ..output(90, H256::from(alice_pub_key)) }],
+			), &alice_pub_key);
+			let locked = BlakeTwo256::hash_of(&(&transaction.encode(), 0 as u64));
+			assert_ok!(Utxo::spend(Origin::signed(0), transaction));
+			assert_eq!(Utxo::created_at(locked), 2);
+
+			let transaction = sign_transaction(paying(
+				vec![input(locked)],
+				vec![output(80, H256::repeat_byte(2))],
+			), &alice_pub_key);
+
+			system::Module::<Test>::set_block_number(6);
+			assert_eq!(Utxo::validate_transaction(&transaction), Err(UtxoError::RelativeLockNotReached));
+
+			system::Module::<Test>::set_block_number(7);
+			assert_ok!(Utxo::spend(Origin::signed(0), transaction));
+			assert!(!CreatedAt::contains_key(locked));
+		});
+	}
+
+	#[test]
+	fn test_signature_bound_to_genesis_hash() {
+		new_test_ext().execute_with(|| {
+			let alice_pub_key = sp_io::crypto::sr25519_public_keys(SR25519)[0];
+			let mut transaction = paying(
+				vec![input(H256::from(GENESIS_UTXO))],
+				vec![output(90, H256::repeat_byte(2))],
+			);
+
+			// signed for a chain with another genesis block
+			let other_chain = (H256::repeat_byte(1), &transaction).encode();
+			let signature = sp_io::crypto::sr25519_sign(SR25519, &alice_pub_key, &other_chain).unwrap();
+			transaction.inputs[0].sigscript = H512::from(signature);
+			assert!(!Utxo::verify_input_signature(&transaction, 0, H256::from(alice_pub_key)));
+			assert_eq!(Utxo::validate_transaction(&transaction), Err(UtxoError::BadSignature));
+
+			let genesis_hash = system::Module::<Test>::block_hash(0);
+			assert!(Utxo::get_simple_transaction(&transaction).starts_with(genesis_hash.as_bytes()));
+			let transaction = sign_transaction(transaction, &alice_pub_key);
+			assert_ok!(Utxo::spend(Origin::signed(0), transaction));
+		});
+	}
+
+	#[test]
+	fn test_batch_spend() {
+		new_test_ext().execute_with(|| {
+			let alice_pub_key = sp_io::crypto::sr25519_public_keys(SR25519)[0];
+			let alice = H256::from(alice_pub_key);
+			seed_utxos(alice, &[50, 60]);
+			let paying = |outpoint: H256, value: Value, to: H256| sign_transaction(paying(
+				vec![input(outpoint)],
+				vec![output(value, to)],
+			), &alice_pub_key);
+			let outpoint = |transaction: &Transaction| BlakeTwo256::hash_of(&(&transaction.encode(), 0 as u64));
+
+			// independent spends
+			let first = paying(H256::repeat_byte(1), 45, alice);
+			let second = paying(H256::repeat_byte(2), 55, alice);
+			assert_ok!(Utxo::batch_spend(Origin::signed(0), vec![first.clone(), second.clone()]));
+			assert!(UtxoStore::contains_key(outpoint(&first)));
+			assert!(UtxoStore::contains_key(outpoint(&second)));
+			assert_eq!(Utxo::total_fees_collected(), 10);
+
+			// the second transaction spends what the first creates
+			let chained_first = paying(outpoint(&first), 40, alice);
+			let chained_second = paying(outpoint(&chained_first), 35, H256::repeat_byte(3));
+			// in the pool the batch requires nothing it creates itself
+			let valid = Utxo::validate_batch(&[chained_first.clone(), chained_second.clone()]).unwrap();
+			assert!(valid.requires.is_empty());
+			assert!(valid.provides.contains(&Utxo::spend_tag(&outpoint(&first))));
+			assert!(valid.provides.contains(&Utxo::spend_tag(&outpoint(&chained_first))));
+			assert!(valid.provides.contains(&outpoint(&chained_second).as_fixed_bytes().to_vec()));
+			assert_ok!(Utxo::batch_spend(Origin::signed(0), vec![chained_first.clone(), chained_second.clone()]));
+			assert!(!UtxoStore::contains_key(outpoint(&chained_first)));
+			assert!(UtxoStore::contains_key(outpoint(&chained_second)));
+
+			// one invalid transaction rejects the whole batch, double spends included
+			let valid = paying(outpoint(&second), 50, alice);
+			let overspending = paying(outpoint(&valid), 100, alice);
+			assert_err!(
+				Utxo::batch_spend(Origin::signed(0), vec![valid.clone(), overspending]),
+				"output value must not exceed input value"
+			);
+			let double_spend = paying(outpoint(&second), 45, alice);
+			assert_eq!(
+				Utxo::validate_batch(&[valid.clone(), double_spend.clone()]).err(),
+				Some(UtxoError::DuplicateInput)
+			);
+			assert_err!(
+				Utxo::batch_spend(Origin::signed(0), vec![valid.clone(), double_spend]),
+				"utxo does not exist"
+			);
+			assert!(UtxoStore::contains_key(outpoint(&second)));
+			assert!(!UtxoStore::contains_key(outpoint(&valid)));
+		});
+	}
+
+	#[test]
+	fn test_authorities_cache() {
+		new_test_ext().execute_with(|| {
+			use sp_runtime::traits::OnFinalize;
+			let authorities = [H256::repeat_byte(1), H256::repeat_byte(2)];
+			set_authorities(&authorities);
+			AUTHORITY_REBUILDS.with(|rebuilds| rebuilds.set(0));
+
+			for block in 1..=3 {
+				system::Module::<Test>::set_block_number(block);
+				RewardTotal::put(10);
+				Utxo::on_finalize(block);
+				for authority in &authorities {
+					let reward = TransactionOutput { value: 5, pubkey: *authority, ..Default::default() };
+					let hash = BlakeTwo256::hash_of(&(&reward, block as u64));
+					assert_eq!(UtxoStore::get(hash), Some(reward));
+				}
+			}
+			// converted once, then reused while the set stays the same
+			assert_eq!(AUTHORITY_REBUILDS.with(|rebuilds| rebuilds.get()), 1);
+			assert_eq!(Utxo::cached_authorities(), authorities.to_vec());
+
+			set_authorities(&authorities[..1]);
+			Utxo::on_finalize(4);
+			assert_eq!(AUTHORITY_REBUILDS.with(|rebuilds| rebuilds.get()), 2);
+			assert_eq!(Utxo::cached_authorities(), vec![authorities[0]]);
+		});
+	}
+
+	#[test]
+	fn test_data_output() {
+		new_test_ext().execute_with(|| {
+			let alice_pub_key = sp_io::crypto::sr25519_public_keys(SR25519)[0];
+			let with_data = |data: Vec<u8>| sign_transaction(paying(
+				vec![input(H256::from(GENESIS_UTXO))],
+				vec![
+					TransactionOutput { lock: Lock::Data { data }, ..output(0, H256::zero()) },
+					output(90, H256::from(alice_pub_key)),
+				],
+			), &alice_pub_key);
+
+			let too_long = with_data(vec![1; 9]);
+			assert_eq!(Utxo::validate_transaction(&too_long), Err(UtxoError::InvalidDataOutput));
+			let mut with_value = with_data(vec![1; 8]);
+			with_value.outputs[0].value = 5;
+			assert_eq!(Utxo::validate_transaction(&with_value), Err(UtxoError::InvalidDataOutput));
+
+			let anchoring = with_data(vec![1; 8]);
+			let data_hash = BlakeTwo256::hash_of(&(&anchoring.encode(), 0 as u64));
+			let value_hash = BlakeTwo256::hash_of(&(&anchoring.encode(), 1 as u64));
+			assert_ok!(Utxo::spend(Origin::signed(0), anchoring.clone()));
+			assert!(!UtxoStore::contains_key(data_hash));
+			assert!(UtxoStore::contains_key(value_hash));
+
+			// the data is committed through the transaction in the success event, and only the
+			// value output and the treasury's cut are created
+			let treasury_hash = BlakeTwo256::hash_of(&(&anchoring.encode(), 2 as u64));
+			assert_eq!(
+				system::Module::<Test>::events().pop().map(|record| record.event),
+				Some(TestEvent::utxo(Event::TransactionSuccess(anchoring, 10, vec![value_hash, treasury_hash])))
+			);
+		});
+	}
+
+	#[test]
+	fn test_treasury_cut_rounding() {
+		new_test_ext_without_signatures().execute_with(|| {
+			TREASURY_SHARE.with(|v| *v.borrow_mut() = Perbill::from_percent(20));
+			seed_utxos(H256::repeat_byte(1), &[100, 100]);
+			let paying_fee = |outpoint: H256| paying(
+				vec![input(outpoint)],
+				vec![output(87, H256::repeat_byte(2))],
+			);
+
+			// 20% of 13 is 2.6: the treasury gets 2, the pool 11, and 0.6 carries over
+			assert_ok!(Utxo::spend(Origin::signed(0), paying_fee(H256::repeat_byte(1))));
+			assert_eq!(Utxo::treasury_balance(), 2);
+			assert_eq!(Utxo::reward_total(), 11);
+			assert_eq!(Utxo::reward_dust(), REWARD_PRECISION * 6 / 10);
+
+			// 2.6 plus the carried 0.6 is 3.2: 3 more to the treasury, 10 to the pool
+			assert_ok!(Utxo::spend(Origin::signed(0), paying_fee(H256::repeat_byte(2))));
+			assert_eq!(Utxo::treasury_balance(), 5);
+			assert_eq!(Utxo::reward_total(), 21);
+			assert_eq!(Utxo::reward_dust(), REWARD_PRECISION * 2 / 10);
+		});
+	}
+
+	#[test]
+	fn test_congestion_multiplier() {
+		new_test_ext().execute_with(|| {
+			use sp_runtime::traits::OnFinalize;
+			MINIMUM_FEE.with(|v| *v.borrow_mut() = 5);
+			let alice_pub_key = sp_io::crypto::sr25519_public_keys(SR25519)[0];
+			let paying = |value| sign_transaction(paying(
+				vec![input(H256::from(GENESIS_UTXO))],
+				vec![output(value, H256::from(alice_pub_key))],
+			), &alice_pub_key);
+			let finish_blocks = |count: u32, blocks: usize| for _ in 0..blocks {
+				BlockTransactions::put(count);
+				<Utxo as OnFinalize<u64>>::on_finalize(1);
+			};
+			assert_eq!(Utxo::congestion_multiplier(), 1);
+			assert!(Utxo::validate_transaction(&paying(95)).is_ok());
+
+			// 4 transactions a block is twice the target of 2
+			finish_blocks(4, CONGESTION_WINDOW);
+			assert_eq!(Utxo::congestion_multiplier(), 3);
+			assert_eq!(Utxo::validate_transaction(&paying(95)), Err(UtxoError::FeeTooLow));
+			assert!(Utxo::validate_transaction(&paying(85)).is_ok());
+
+			// quiet blocks push the busy ones out of the window
+			finish_blocks(0, CONGESTION_WINDOW / 2);
+			assert_eq!(Utxo::congestion_multiplier(), 2);
+			finish_blocks(0, CONGESTION_WINDOW / 2);
+			assert_eq!(Utxo::congestion_multiplier(), 1);
+			assert!(Utxo::validate_transaction(&paying(95)).is_ok());
+
+			// applied transactions are what gets counted
+			assert_ok!(Utxo::spend(Origin::signed(0), paying(95)));
+			assert_eq!(Utxo::block_transactions(), 1);
+			<Utxo as OnFinalize<u64>>::on_finalize(1);
+			assert_eq!(Utxo::block_transactions(), 0);
+			assert_eq!(Utxo::recent_transactions().last(), Some(&1));
+		});
+	}
+
+	#[test]
+	fn test_spend_revalidates_once_inputs_exist() {
+		new_test_ext().execute_with(|| {
+			let alice_pub_key = sp_io::crypto::sr25519_public_keys(SR25519)[0];
+			let paying = |outpoint, value| sign_transaction(paying(
+				vec![input(outpoint)],
+				vec![output(value, H256::from(alice_pub_key))],
+			), &alice_pub_key);
+			let parent = paying(H256::from(GENESIS_UTXO), 90);
+			let parent_utxo = BlakeTwo256::hash_of(&(&parent.encode(), 0 as u64));
+			let child = paying(parent_utxo, 75);
+			let overspending_child = paying(parent_utxo, 95);
+
+			// while the parent is pending, neither child's value can be checked
+			let pending = Utxo::validate_transaction(&child).unwrap();
+			assert_eq!(pending.requires, vec![parent_utxo.as_fixed_bytes().to_vec()]);
+			assert_eq!(pending.priority, 0);
+			assert!(Utxo::validate_transaction(&overspending_child).is_ok());
+			assert_err!(Utxo::spend(Origin::signed(0), child.clone()), "utxo does not exist");
+
+			assert_ok!(Utxo::spend(Origin::signed(0), parent));
+			assert_eq!(Utxo::validate_transaction(&child).map(|valid| valid.priority), Ok(15));
+			assert_err!(
+				Utxo::spend(Origin::signed(0), overspending_child),
+				"output value must not exceed input value"
+			);
+			assert_ok!(Utxo::spend(Origin::signed(0), child.clone()));
+			assert_eq!(Utxo::total_fees_collected(), 25);
+			assert_eq!(
+				system::Module::<Test>::events().pop().map(|record| record.event),
+				Some(TestEvent::utxo(Event::TransactionSuccess(
+					child.clone(),
+					15,
+					// its output, then the treasury's cut
+					vec![
+						BlakeTwo256::hash_of(&(&child.encode(), 0 as u64)),
+						BlakeTwo256::hash_of(&(&child.encode(), 1 as u64)),
+					],
+				)))
+			);
+		});
+	}
+
+	#[test]
+	fn test_consolidate() {
+		new_test_ext().execute_with(|| {
+			MINIMUM_FEE.with(|v| *v.borrow_mut() = 5);
+			MIN_RELAY_FEE_PER_BYTE.with(|v| *v.borrow_mut() = 1);
+			let alice_pub_key = sp_io::crypto::sr25519_public_keys(SR25519)[0];
+			let alice = H256::from(alice_pub_key);
+			let dest = H256::repeat_byte(7);
+			seed_utxos(alice, &[100, 200, 300]);
+			let inputs: Vec<TransactionInput> = (1..=3).map(|byte| input(H256::repeat_byte(byte))).collect();
+			let consolidating = |fee| sign_transaction(Utxo::consolidating(inputs.clone(), dest, fee).unwrap(), &alice_pub_key);
+
+			assert_err!(Utxo::consolidate(Origin::signed(0), inputs.clone(), dest, 5), "signature must be valid");
+			assert_err!(Utxo::consolidating(inputs.clone(), dest, 601), UtxoError::OutputExceedsInput);
+
+			// the flat minimum alone doesn't cover the transaction's size
+			let underpaying = consolidating(5);
+			let fee = underpaying.encode().len() as Value;
+			assert_err!(
+				Utxo::consolidate(Origin::signed(0), underpaying.inputs, dest, 5),
+				"fee below the relay minimum for its size"
+			);
+
+			let transaction = consolidating(fee);
+			assert_ok!(Utxo::consolidate(Origin::signed(0), transaction.inputs.clone(), dest, fee));
+			for byte in 1..=3 {
+				assert!(!UtxoStore::contains_key(H256::repeat_byte(byte)));
+			}
+			let merged = BlakeTwo256::hash_of(&(&transaction.encode(), 0 as u64));
+			assert_eq!(UtxoStore::get(merged), Some(output(600 - fee, dest)));
+			assert_eq!(Utxo::total_fees_collected(), fee);
+		});
+	}
+
+	#[test]
+	fn test_input_and_output_caps() {
+		let spending = |inputs: u8, outputs: u8| paying(
+			(1..=inputs).map(|byte| input(H256::repeat_byte(byte))).collect(),
+			(1..=outputs).map(|byte| output(10, H256::repeat_byte(byte))).collect(),
+		);
+
+		new_test_ext_without_signatures().execute_with(|| {
+			seed_utxos(H256::repeat_byte(1), &[1; 65]);
+			assert!(Utxo::validate_transaction(&spending(64, 1)).is_ok());
+			seed_utxos(H256::repeat_byte(1), &[200]);
+			assert!(Utxo::validate_transaction(&spending(1, 16)).is_ok());
+
+			// rejected before any signing payload is built
+			SIMPLE_TRANSACTION_CALLS.with(|calls| calls.set(0));
+			assert_eq!(Utxo::validate_transaction(&spending(65, 1)).err(), Some(UtxoError::TooManyInputs));
+			assert_eq!(Utxo::validate_transaction(&spending(1, 17)).err(), Some(UtxoError::TooManyOutputs));
+			assert_eq!(SIMPLE_TRANSACTION_CALLS.with(|calls| calls.get()), 0);
+			assert_err!(Utxo::spend(Origin::signed(0), spending(65, 1)), "too many inputs");
+			assert_err!(Utxo::spend(Origin::signed(0), spending(1, 17)), "too many outputs");
+		});
+	}
+
+	#[test]
+	fn test_reward_remainder_carries_over() {
+		new_test_ext().execute_with(|| {
+			let authorities = vec![H256::repeat_byte(1), H256::repeat_byte(2), H256::repeat_byte(3)];
+			let received = |authority: &H256| Utxo::utxos_of(*authority)
+				.iter()
+				.fold(0, |total: Value, (_, utxo)| total + utxo.value);
+
+			// 7 a block: 2 each and 1 left, then 8 gives 2 each and 2 left, then 9 gives 3 each
+			for (block, share, remainder) in vec![(1, 2, 1), (2, 2, 2), (3, 3, 0)] {
+				system::Module::<Test>::set_block_number(block);
+				RewardTotal::mutate(|total| *total += 7);
+				Utxo::disperse_rewards(&authorities);
+				assert_eq!(
+					system::Module::<Test>::events().pop().map(|record| record.event),
+					Some(TestEvent::utxo(Event::RewardDispersed(block, share, authorities.clone())))
+				);
+				assert_eq!(Utxo::reward_total(), remainder);
+			}
+			// the 21 paid over the three blocks ends up split evenly
+			assert!(authorities.iter().all(|authority| received(authority) == 7));
+		});
+	}
+
+	#[test]
+	fn test_dispersal_remainder_overflow() {
+		assert_eq!(Utxo::dispersal_remainder(7, 2, 3), Some(1));
+		assert_eq!(Utxo::dispersal_remainder(Value::MAX, Value::MAX, 1), Some(0));
+		assert_eq!(Utxo::dispersal_remainder(Value::MAX, Value::MAX / 2 + 1, 2), None);
+	}
+
+	#[test]
+	fn test_outpoint_signed_twice_is_duplicate() {
+		new_test_ext().execute_with(|| {
+			let alice_pub_key = sp_io::crypto::sr25519_public_keys(SR25519)[0];
+			let genesis = H256::from(GENESIS_UTXO);
+			let mut transaction = paying(
+				vec![input(genesis), input(genesis)],
+				vec![output(150, H256::from(alice_pub_key))],
+			);
+			// sr25519 signatures are randomized, so the two inputs differ only in their sigscripts
+			let simple_transaction = Utxo::get_simple_transaction(&transaction);
+			for input in transaction.inputs.iter_mut() {
+				input.sigscript = H512::from(sp_io::crypto::sr25519_sign(SR25519, &alice_pub_key, &simple_transaction).unwrap());
+			}
+			assert_ne!(transaction.inputs[0], transaction.inputs[1]);
+
+			assert_eq!(Utxo::validate_transaction(&transaction), Err(UtxoError::DuplicateInput));
+			assert_err!(Utxo::spend(Origin::signed(0), transaction), "each input must only be used once");
+			assert_eq!(Utxo::total_supply(), 100);
+		});
+	}
+
+	#[test]
+	fn test_can_spend_checks_locks() {
+		new_test_ext().execute_with(|| {
+			let alice = H256::from(sp_io::crypto::sr25519_public_keys(SR25519)[0]);
+			let (time_locked, relative, reward) = (H256::repeat_byte(1), H256::repeat_byte(2), H256::repeat_byte(3));
+			UtxoStore::insert(time_locked, TransactionOutput { lock_until: Some(5), ..output(50, alice) });
+			UtxoStore::insert(relative, TransactionOutput { relative_lock: 5, ..output(50, alice) });
+			CreatedAt::insert(relative, 2);
+			UtxoStore::insert(reward, output(50, alice));
+			RewardMaturity::insert(reward, 2);
+
+			assert_err!(Utxo::can_spend(time_locked, alice, 4), UtxoError::OutputLocked);
+			assert_ok!(Utxo::can_spend(time_locked, alice, 5));
+			assert_err!(Utxo::can_spend(relative, alice, 6), UtxoError::RelativeLockNotReached);
+			assert_ok!(Utxo::can_spend(relative, alice, 7));
+			// rewards mature after `MaturityPeriod` blocks
+			assert_err!(Utxo::can_spend(reward, alice, 4), UtxoError::RewardImmature);
+			assert_ok!(Utxo::can_spend(reward, alice, 5));
+		});
+	}
+
+	#[test]
+	fn test_data_only_transaction_pays_minimum_fee() {
+		new_test_ext().execute_with(|| {
+			MINIMUM_FEE.with(|v| *v.borrow_mut() = 5);
+			let alice_pub_key = sp_io::crypto::sr25519_public_keys(SR25519)[0];
+			seed_utxos(H256::from(alice_pub_key), &[5, 60]);
+			let anchoring = |outpoint| sign_transaction(paying(
+				vec![input(outpoint)],
+				vec![TransactionOutput { lock: Lock::Data { data: vec![1; 8] }, ..output(0, H256::zero()) }],
+			), &alice_pub_key);
+
+			// at most `MaxFeeRatio` times the required fee, as nothing is paid out
+			assert_eq!(Utxo::validate_transaction(&anchoring(H256::repeat_byte(2))), Err(UtxoError::ExcessiveFee));
+
+			let exact = anchoring(H256::repeat_byte(1));
+			assert_eq!(Utxo::validate_transaction(&exact).map(|valid| valid.priority), Ok(5));
+			assert_ok!(Utxo::spend(Origin::signed(0), exact));
+			assert_eq!(Utxo::total_fees_collected(), 5);
+		});
+	}
+
+	#[test]
+	fn test_multisig_key_and_witness_caps() {
+		new_test_ext().execute_with(|| {
+			let key_pairs: Vec<_> = (0..5).map(|_| sp_io::crypto::sr25519_generate(SR25519, None)).collect();
+			let keys: Vec<H256> = key_pairs.iter().map(|key| H256::from(*key)).collect();
+			let multisig = |count: usize| Lock::MultiSig { threshold: count as u8, keys: keys[..count].to_vec() };
+			let owner = H256::from(sp_io::crypto::sr25519_public_keys(SR25519)[0]);
+			let locked = H256::repeat_byte(1);
+			UtxoStore::insert(locked, TransactionOutput { lock: multisig(4), ..output(50, owner) });
+			UtxoStore::insert(H256::repeat_byte(2), TransactionOutput { lock: multisig(2), ..output(50, owner) });
+
+			// at most `MaxMultiSigKeys` keys per output
+			let creating = |lock| paying(vec![input(locked)], vec![TransactionOutput { lock, ..output(40, owner) }]);
+			assert_eq!(Utxo::structural_check(&creating(multisig(4))), Ok(()));
+			assert_eq!(Utxo::structural_check(&creating(multisig(5))), Err(UtxoError::InvalidMultiSig));
+
+			// every key of the four signs, the last one three times: one witness over the cap
+			let mut transaction = paying(vec![input(locked)], vec![output(40, owner)]);
+			let simple_transaction = Utxo::get_simple_transaction(&transaction);
+			let signatures: Vec<H512> = key_pairs[..4].iter()
+				.map(|key| H512::from(sp_io::crypto::sr25519_sign(SR25519, key, &simple_transaction).unwrap()))
+				.collect();
+			transaction.inputs[0].sigscript = signatures[0];
+			transaction.inputs[0].witness = signatures[1..].to_vec();
+			transaction.inputs[0].witness.extend(vec![signatures[3]; 2]);
+			assert_eq!(Utxo::validate_transaction(&transaction).err(), Some(UtxoError::TooManySignatures));
+
+			// more witness signatures than the spent output has keys
+			let two_keys = TransactionInput { witness: signatures[1..4].to_vec(), ..input(H256::repeat_byte(2)) };
+			let overloaded = Transaction { inputs: vec![two_keys], ..transaction.clone() };
+			assert_eq!(Utxo::validate_transaction(&overloaded).err(), Some(UtxoError::TooManySignatures));
+
+			// at the cap, and as many witness signatures as keys
+			transaction.inputs[0].witness.pop();
+			assert!(Utxo::validate_transaction(&transaction).is_ok());
+			assert_ok!(Utxo::spend(Origin::signed(0), transaction));
+		});
+	}
 }
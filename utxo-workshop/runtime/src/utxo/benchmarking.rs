@@ -0,0 +1,65 @@
+//! UTXO module benchmarking, used to derive the `SpendWeight` coefficients.
+
+use super::*;
+
+use frame_benchmarking::benchmarks;
+use sp_core::crypto::KeyTypeId;
+use system::RawOrigin;
+
+use crate::utxo::Module as Utxo;
+
+const KEY_TYPE: KeyTypeId = KeyTypeId(*b"utxo");
+const MAX_INPUTS: u32 = 100;
+const MAX_OUTPUTS: u32 = 100;
+const INPUT_VALUE: Value = 1_000_000;
+
+/// A signed transaction spending `inputs` freshly stored UTXOs into `outputs` distinct outputs.
+fn signed_transaction<T: Trait>(inputs: u32, outputs: u32) -> Transaction {
+	let owner = sp_io::crypto::sr25519_generate(KEY_TYPE, None);
+	let mut transaction = Transaction {
+		valid_until: u64::max_value(),
+		..Default::default()
+	};
+
+	for i in 0..inputs {
+		let outpoint = BlakeTwo256::hash_of(&(b"bench", i));
+		<Utxo<T>>::insert_utxo(outpoint, TransactionOutput {
+			value: INPUT_VALUE,
+			pubkey: H256::from(owner),
+			..Default::default()
+		});
+		transaction.inputs.push(TransactionInput { outpoint, sigscript: H512::zero(), witness: Vec::new() });
+	}
+	<TotalSupply>::put(INPUT_VALUE * inputs as Value);
+
+	// Ascending pubkeys keep the outputs distinct and canonically ordered.
+	let value = INPUT_VALUE * inputs as Value / outputs as Value;
+	for o in 0..outputs {
+		transaction.outputs.push(TransactionOutput {
+			value,
+			pubkey: H256::from_low_u64_be(o as u64 + 1),
+			..Default::default()
+		});
+	}
+
+	let simple_transaction = <Utxo<T>>::get_simple_transaction(&transaction);
+	let signature = sp_io::crypto::sr25519_sign(KEY_TYPE, &owner, &simple_transaction)
+		.expect("key was just generated; qed");
+	for input in transaction.inputs.iter_mut() {
+		input.sigscript = H512::from(signature.clone());
+	}
+	transaction
+}
+
+benchmarks! {
+	_ {
+		let i in 1 .. MAX_INPUTS => ();
+		let o in 1 .. MAX_OUTPUTS => ();
+	}
+
+	spend {
+		let i in ...;
+		let o in ...;
+		let transaction = signed_transaction::<T>(i, o);
+	}: _(RawOrigin::Signed(Default::default()), transaction)
+}